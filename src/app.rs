@@ -12,10 +12,17 @@ use tracing::{debug, info};
 
 use crate::auto_roll::{self, AutoRollConfig, AutoRollMod};
 use crate::chaos_recipe;
+use crate::inventory::InvItem;
+use crate::loot_filter;
 use crate::screenshot::{Rect, ScreenshotData};
-use crate::stash_grid::{CellGrid, MAP_COLS, MAP_ROWS, QUAD_COLS, QUAD_ROWS};
+use crate::stash_grid::{
+    CellGrid, MAP_COLS, MAP_ROWS, NORMAL_COLS, NORMAL_ROWS, QUAD_COLS, QUAD_ROWS, TabGrid,
+};
 use crate::{NamedPoint, ScreenRegion, Settings, config_path, save_config};
 
+/// Screenshots `reset_inv_colors` averages each probe over.
+const INV_SAMPLE_FRAMES: usize = 3;
+
 /// The injected context: every command is a method on `App`.
 ///
 /// `App` owns all state that used to live in process globals:
@@ -29,6 +36,8 @@ pub struct App {
     pub(crate) settings: RwLock<Settings>,
     /// Cross-platform input backend (mouse + keyboard), constructed once.
     input: Mutex<Input>,
+    /// Item footprints from the last inventory read, for the GUI overlay.
+    pub(crate) inv_items: Mutex<Vec<InvItem>>,
 }
 
 impl App {
@@ -38,6 +47,7 @@ impl App {
         Ok(Self {
             settings: RwLock::new(settings),
             input: Mutex::new(input),
+            inv_items: Mutex::new(Vec::new()),
         })
     }
 
@@ -225,7 +235,13 @@ impl App {
             width: before.width as u32,
             height: before.height as u32,
         };
-        let clusters = crate::screenshot::diff_clusters(&before, &after, bounds, 4)?;
+        let clusters = crate::screenshot::diff_clusters(
+            &before,
+            &after,
+            bounds,
+            4,
+            self.settings.read().colour_matcher(),
+        )?;
         let [a, b] = clusters.as_slice() else {
             bail!(
                 "Expected exactly 2 changed regions (cursor before and after), found {}. \
@@ -255,7 +271,7 @@ impl App {
         {
             let mut settings = self.settings.write();
             settings.pointer_scale = Some(scale);
-            save_config(&config_path()?, &*settings)?;
+            save_config(&config_path()?, &settings.to_disk()?)?;
         }
         println!("pointer_scale = {scale:.4} (was measured over {D} device units)");
         Ok(())
@@ -264,13 +280,15 @@ impl App {
     /// Generic grid calibration: capture three cells (TOP-LEFT, BOTTOM-RIGHT, any
     /// MIDDLE), each with a nonsense-search base frame and a real-search capture,
     /// then derive the grid from the two corner cells and validate against the
-    /// middle cell. `label`/`noun` drive the prompts; `save` persists the grid.
+    /// middle cell. `label`/`noun` drive the prompts, `region_name` is the
+    /// `set-region` the grid lives in; `save` persists the grid.
     fn calibrate_grid<const C: usize, const R: usize>(
         &self,
         snapshot: &Settings,
         region: ScreenRegion,
-        label: &str, // "stash" | "map"
-        noun: &str,  // "item" | "map"
+        region_name: &str, // "stash" | "map"
+        label: &str,       // "stash" | "normal" | "map"
+        noun: &str,        // "item" | "map"
         save: impl FnOnce(&mut Settings, CellGrid<C, R>),
     ) -> anyhow::Result<()> {
         prompt_enter(&format!("Open the {label} tab."))?;
@@ -295,7 +313,7 @@ impl App {
                 // Convert region from screen space to frame-pixel space.
                 let (bx, by) = base.screen_to_frame(region.x, region.y).ok_or_else(|| {
                 anyhow::anyhow!(
-                    "{label} region starts outside the game window region — re-run set-region window and set-region {label}"
+                    "{label} region starts outside the game window region — re-run set-region window and set-region {region_name}"
                 )
             })?;
                 let bounds = Rect {
@@ -311,7 +329,13 @@ impl App {
                 std::thread::sleep(std::time::Duration::from_millis(300));
                 let cap = snapshot.screenshot()?;
 
-                let clusters = crate::screenshot::diff_clusters(&base, &cap, bounds, 20)?;
+                let clusters = crate::screenshot::diff_clusters(
+                    &base,
+                    &cap,
+                    bounds,
+                    20,
+                    snapshot.colour_matcher(),
+                )?;
                 let cell = clusters
                 .iter()
                 .max_by_key(|r| r.width * r.height)
@@ -326,28 +350,42 @@ impl App {
 
         let (base, tl, _bounds, tl_cell) = capture_pos("TOP-LEFT")?;
 
-        // Derive highlight_color from the tl cell's bottom boundary row.
+        // Derive highlight_color from the tl cell's bottom boundary row: the
+        // most common changed colour, refined to the mean of the changed
+        // pixels near it, whose spread the grid tolerates on top of
+        // `colour_tolerance`.
         use std::collections::HashMap;
-        let mut color_tally: HashMap<u32, u32> = HashMap::new();
+        let matcher = snapshot.colour_matcher();
+        let mut changed: Vec<u32> = Vec::new();
         let by_abs = (tl_cell.y + tl_cell.height.saturating_sub(1)) as usize;
         for x in tl_cell.x as usize..(tl_cell.x + tl_cell.width) as usize {
-            if base.try_get_pixel(x, by_abs) != tl.try_get_pixel(x, by_abs)
-                && let Some(c) = tl.try_get_pixel(x, by_abs)
+            if let (Some(b), Some(c)) = (base.try_get_pixel(x, by_abs), tl.try_get_pixel(x, by_abs))
+                && matcher.differs(b, c)
             {
-                *color_tally.entry(c).or_insert(0) += 1;
+                changed.push(c);
             }
         }
-        let highlight_color = color_tally
+        let mut color_tally: HashMap<u32, u32> = HashMap::new();
+        for &c in &changed {
+            *color_tally.entry(c).or_insert(0) += 1;
+        }
+        let mode = color_tally
             .into_iter()
             .max_by_key(|(_, count)| *count)
             .map(|(c, _)| c)
             .ok_or_else(|| anyhow::anyhow!(
                 "Could not determine highlight color — the highlighted cell's bottom edge did not change"
             ))?;
+        let near: Vec<u32> = changed
+            .into_iter()
+            .filter(|&c| matcher.matches(c, mode, matcher.tolerance))
+            .collect();
+        let (highlight_color, highlight_spread) = crate::colour::stats(&near);
 
         let (_base2, _br, _bounds2, br_cell) = capture_pos("BOTTOM-RIGHT")?;
 
-        let grid = CellGrid::<C, R>::from_corners(tl_cell, br_cell, highlight_color)?;
+        let mut grid = CellGrid::<C, R>::from_corners(tl_cell, br_cell, highlight_color)?;
+        grid.highlight_spread = highlight_spread;
 
         let (_base3, mid, _bounds3, mid_cell) = capture_pos("any MIDDLE")?;
 
@@ -386,7 +424,7 @@ impl App {
             );
         }
 
-        if !grid.is_highlighted(&mid, best_col, best_row) {
+        if !grid.is_highlighted(&mid, best_col, best_row, snapshot.colour_matcher()) {
             bail!(
                 "Computed grid found the middle cell but its bottom edge does not match the calibrated highlight color — re-run calibration"
             );
@@ -403,7 +441,7 @@ impl App {
         {
             let mut settings = self.settings.write();
             save(&mut settings, grid);
-            save_config(&config_path()?, &*settings)?;
+            save_config(&config_path()?, &settings.to_disk()?)?;
         }
 
         println!(
@@ -426,11 +464,34 @@ impl App {
             &snapshot,
             stash_region,
             "stash",
+            "stash",
             "item",
             |s, g| s.stash_grid = Some(g),
         )
     }
 
+    /// Same three-position flow as `calibrate_stash`, for a normal (12x12)
+    /// tab. Premium normal tabs share the stash region with quad tabs.
+    fn calibrate_normal(&self) -> anyhow::Result<()> {
+        let snapshot = { self.settings.read().clone() };
+
+        let _game_region = snapshot.game_window_region.ok_or_else(|| {
+            anyhow::anyhow!("Game window region not set — run: little_oil set-region window")
+        })?;
+        let stash_region = snapshot.stash_region.ok_or_else(|| {
+            anyhow::anyhow!("Stash region not set — run: little_oil set-region stash")
+        })?;
+
+        self.calibrate_grid::<NORMAL_COLS, NORMAL_ROWS>(
+            &snapshot,
+            stash_region,
+            "stash",
+            "normal",
+            "item",
+            |s, g| s.normal_grid = Some(g),
+        )
+    }
+
     fn calibrate_map(&self) -> anyhow::Result<()> {
         let snapshot = { self.settings.read().clone() };
 
@@ -441,9 +502,14 @@ impl App {
             anyhow::anyhow!("Map region not set — run: little_oil set-region map")
         })?;
 
-        self.calibrate_grid::<MAP_COLS, MAP_ROWS>(&snapshot, map_region, "map", "map", |s, g| {
-            s.map_grid = Some(g)
-        })
+        self.calibrate_grid::<MAP_COLS, MAP_ROWS>(
+            &snapshot,
+            map_region,
+            "map",
+            "map",
+            "map",
+            |s, g| s.map_grid = Some(g),
+        )
     }
 
     /// Slurp one box around a named clickable target and upsert it into `points`.
@@ -462,11 +528,30 @@ impl App {
             region,
         });
         settings.points = Some(points);
-        save_config(&config_path()?, &*settings)?;
+        save_config(&config_path()?, &settings.to_disk()?)?;
         println!("Point '{name}' saved at ({}, {})", region.x, region.y);
         Ok(())
     }
 
+    /// Calibrate the Divination Card tab for `div-turnin`: slurp the box
+    /// around one page's card slots as the `div-tab` point, then save its
+    /// `cols` x `rows` slot count. `div-turnin` splits that box evenly, so
+    /// only the open page is covered.
+    fn calibrate_div(&self, cols: u32, rows: u32) -> anyhow::Result<()> {
+        if cols == 0 || rows == 0 {
+            bail!("The div tab needs at least one column and one row of card slots");
+        }
+        prompt_enter(
+            "Open the Divination Card tab. Next: drag a box from the top-left card slot to the bottom-right one.",
+        )?;
+        self.calibrate_point("div-tab")?;
+        let mut settings = self.settings.write();
+        settings.div_tab_slots = Some([cols, rows]);
+        save_config(&config_path()?, &settings.to_disk()?)?;
+        println!("Div tab saved: {cols} x {rows} card slots per page");
+        Ok(())
+    }
+
     /// The screen point we normally click to focus the game: bottom-middle of
     /// the inventory panel (falling back to the game window). Parking the
     /// cursor here before screenshots keeps no item hovered in the capture.
@@ -498,11 +583,50 @@ impl App {
         Ok(())
     }
 
+    /// Follow the game window if it moved since calibration (niri workspace
+    /// scroll, a dragged window). A window of another size switches to the
+    /// profile calibrated at that size, or errors when there is none. No-op
+    /// when the platform cannot find the window.
+    pub(crate) fn reanchor(&self) -> anyhow::Result<()> {
+        let Some(found) = self.platform().find_game_window() else {
+            return Ok(());
+        };
+        let switched = {
+            let mut settings = self.settings.write();
+            match settings.profile_for_window(found) {
+                Some(name) => {
+                    let kept = settings.use_profile(&name)?;
+                    save_config(&config_path()?, &settings.to_disk()?)?;
+                    Some((name, kept))
+                }
+                None => None,
+            }
+        };
+        if let Some((name, kept)) = switched {
+            println!(
+                "Game window is {}x{} — switched to profile '{name}'",
+                found.width, found.height
+            );
+            if let Some(kept) = kept {
+                println!("The unsaved calibration was kept as profile '{kept}'");
+            }
+        }
+        if self.settings.write().reanchor(found)? {
+            info!(
+                x = found.x,
+                y = found.y,
+                "game window moved, calibration re-anchored"
+            );
+        }
+        Ok(())
+    }
+
     /// Click the bottom-middle of the player inventory panel so the game window
     /// receives keyboard focus before any automation starts. Without this, a
     /// terminal-launched command leaves keyboard focus in the terminal, and the
     /// Ctrl the operator holds (or the empty macro sends) never reaches the game.
     pub(crate) fn focus_game_window(&self) -> anyhow::Result<()> {
+        self.reanchor()?;
         let (sx, sy) = self.focus_click_point()?;
         println!("Focus click at ({sx}, {sy}) — game window should come to the foreground");
         // Click `focus_clicks` times: click-to-focus compositors (Hyprland) consume
@@ -519,25 +643,196 @@ impl App {
         Ok(())
     }
 
-    /// Click a calibrated named point (currency slot, filter button, …).
-    fn click_point(&self, name: &str) -> anyhow::Result<()> {
-        self.focus_game_window()?;
+    /// Screen-space center of a calibrated named point. Unlike [`point_pos`]
+    /// there is no fallback: a missing point is an error naming the fix.
+    pub(crate) fn named_point(&self, name: &str) -> anyhow::Result<(i32, i32)> {
+        let (sx, sy) = self.named_region(name)?.center();
+        Ok((sx as i32, sy as i32))
+    }
+
+    /// The whole slurped box of a calibrated named point, for steps that
+    /// watch an area rather than click its middle.
+    pub(crate) fn named_region(&self, name: &str) -> anyhow::Result<ScreenRegion> {
         let settings = self.settings.read();
-        let point = settings
+        settings
             .points
             .as_ref()
             .and_then(|ps| ps.iter().find(|p| p.name == name))
+            .map(|p| p.region)
             .ok_or_else(|| {
                 anyhow::anyhow!(
                     "No calibrated point named '{name}' — run: little_oil calibrate-point {name}"
                 )
+            })
+    }
+
+    /// Click a calibrated named point (currency slot, filter button, …).
+    pub(crate) fn click_point(&self, name: &str) -> anyhow::Result<()> {
+        self.focus_game_window()?;
+        let (sx, sy) = self.named_point(name)?;
+        self.click(sx, sy);
+        Ok(())
+    }
+
+    /// Ctrl+left-click: the game's "move to the other open panel" click.
+    pub(crate) fn ctrl_click(&self, x: i32, y: i32) {
+        self.input.lock().key(InputKey::Ctrl, true);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        self.click(x, y);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        self.input.lock().key(InputKey::Ctrl, false);
+    }
+
+    /// Run one macro step and confirm it did something: screenshot, `step`,
+    /// wait `pull_delay`, screenshot again and require a change inside
+    /// `watch`, the screen region the step is expected to affect. The rest of
+    /// the scene animates, so it is never looked at. The cursor is parked
+    /// before both captures so a hover tooltip is not mistaken for the
+    /// step's effect.
+    fn verified_step(
+        &self,
+        what: &str,
+        watch: ScreenRegion,
+        step: impl FnOnce() -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let snapshot = { self.settings.read().clone() };
+        self.park_cursor()?;
+        let before = snapshot.screenshot()?;
+        step()?;
+        std::thread::sleep(std::time::Duration::from_millis(snapshot.pull_delay + 150));
+        self.park_cursor()?;
+        let after = snapshot.screenshot()?;
+        if !Self::region_changed(&before, &after, watch, snapshot.colour_matcher())? {
+            bail!("{what}: nothing changed on screen — check the calibrated point and retry");
+        }
+        debug!(what, "step verified");
+        Ok(())
+    }
+
+    /// `load-map`: pull a map out of the map tab and load the device.
+    ///
+    /// Clicks the `map-tier-<tier>` sub-tab (when given), ctrl-clicks the map
+    /// at `cell` (or the first highlighted or occupied cell, see
+    /// `first_map_cell`) into the inventory,
+    /// opens the device via the `map-device` point, then ctrl-clicks the map
+    /// and every `map_device_points` entry into the device slots. Each step is
+    /// verified by a screenshot diff of the area it should change — the map
+    /// region for the tier tab, the inventory for the map leaving it, the
+    /// `map-device-window` box (slurped with calibrate-point) for the device
+    /// and its slots — and the macro stops at the first one that changed
+    /// nothing.
+    fn load_map(&self, tier: Option<&str>, cell: Option<(usize, usize)>) -> anyhow::Result<()> {
+        self.focus_game_window()?;
+        let snapshot = { self.settings.read().clone() };
+        let grid = match &snapshot.map_grid {
+            Some(g) => g.clone(),
+            None => bail!("Map grid not calibrated — run: little_oil calibrate-map"),
+        };
+        let inv_region = snapshot.inv_region.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Inventory region not calibrated — run: little_oil set-region inventory"
+            )
+        })?;
+        let map_region = snapshot.map_region.ok_or_else(|| {
+            anyhow::anyhow!("Map region not set — run: little_oil set-region map")
+        })?;
+        let expected = snapshot.inv_empty_samples()?;
+        // Resolve every point up front so a missing calibration fails before
+        // anything moves.
+        let tab_point = tier
+            .map(|t| self.named_point(&format!("map-tier-{t}")))
+            .transpose()?;
+        let device = self.named_point("map-device")?;
+        let device_window = self.named_region("map-device-window")?;
+        let extras = snapshot
+            .map_device_points
+            .iter()
+            .map(|name| Ok((name.as_str(), self.named_point(name)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if let Some((sx, sy)) = tab_point {
+            self.verified_step("open map tier tab", map_region, || {
+                self.click(sx, sy);
+                Ok(())
             })?;
-        let (sx, sy) = point.region.center();
-        drop(settings);
-        self.click(sx as i32, sy as i32);
+        }
+
+        self.park_cursor()?;
+        let frame = snapshot.screenshot()?;
+        let (col, row) = match cell {
+            Some(c) => c,
+            None => Self::first_map_cell(&snapshot, &grid, &frame)?,
+        };
+        let before =
+            Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+
+        let (px, py) = grid.cell_center(col, row);
+        let (sx, sy) = frame.frame_to_screen(px, py);
+        self.ctrl_click(sx, sy);
+        std::thread::sleep(std::time::Duration::from_millis(snapshot.pull_delay + 150));
+
+        // The map's inventory slot is whichever cell became occupied.
+        self.park_cursor()?;
+        let frame = snapshot.screenshot()?;
+        let after = Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+        let (mx, my) = after
+            .into_iter()
+            .find(|c| !before.contains(c))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "map at ({col}, {row}) did not arrive in the inventory — inventory full, or the cell is empty"
+                )
+            })?;
+        println!("Map ({col}, {row}) pulled to the inventory");
+
+        self.verified_step("open map device", device_window, || {
+            self.click(device.0, device.1);
+            Ok(())
+        })?;
+        self.verified_step("place map in device", inv_region, || {
+            self.ctrl_click(mx, my);
+            Ok(())
+        })?;
+        for (name, (px, py)) in &extras {
+            self.verified_step(&format!("place {name} in device"), device_window, || {
+                self.ctrl_click(*px, *py);
+                Ok(())
+            })?;
+        }
+        println!("Map device loaded: map + {} extra slot(s)", extras.len());
         Ok(())
     }
 
+    /// The map `load-map` pulls when no cell is given: the first
+    /// search-highlighted cell, else the first occupied one (row by row) when
+    /// the empty map tab was sampled with `reset_map`.
+    fn first_map_cell(
+        snapshot: &Settings,
+        grid: &crate::stash_grid::MapGrid,
+        frame: &ScreenshotData,
+    ) -> anyhow::Result<(usize, usize)> {
+        let matcher = snapshot.colour_matcher();
+        let highlighted = (0..MAP_ROWS)
+            .flat_map(|row| (0..MAP_COLS).map(move |col| (col, row)))
+            .find(|&(col, row)| grid.is_highlighted(frame, col, row, matcher));
+        if let Some(cell) = highlighted {
+            return Ok(cell);
+        }
+        let Ok(expected) = snapshot.map_empty_samples() else {
+            bail!(
+                "No highlighted map — search for the map first, run: little_oil reset_map on an empty map tab, or pass: load-map <tier> <col> <row>"
+            );
+        };
+        Self::occupied_grid_cells(frame, grid, &expected)
+            .into_iter()
+            .min_by_key(|&(col, row)| (row, col))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No highlighted or occupied map in the map tab — open the tier's sub-tab, or pass: load-map <tier> <col> <row>"
+                )
+            })
+    }
+
     /// Click cell (col, row) of the calibrated map grid.
     fn click_map_cell(&self, col: usize, row: usize) -> anyhow::Result<()> {
         self.focus_game_window()?;
@@ -554,26 +849,51 @@ impl App {
         Ok(())
     }
 
-    fn stash_copy(&self) -> anyhow::Result<()> {
+    /// The grid for `--tab <name>` in `args`: quad or normal by the tab type
+    /// the stash API reports. Without `--tab`, the quad grid.
+    fn tab_grid_arg(&self, args: &[String]) -> anyhow::Result<TabGrid> {
+        let Some(i) = args.iter().position(|a| a == "--tab") else {
+            return self.settings.read().tab_grid(true);
+        };
+        let name = args
+            .get(i + 1)
+            .ok_or_else(|| anyhow::anyhow!("--tab needs a value"))?;
+        let c = self
+            .settings
+            .read()
+            .chaos_recipe_settings
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+        let quad = chaos_recipe::tab_is_quad(&c, name)?;
+        self.settings.read().tab_grid(quad)
+    }
+
+    /// `stash copy`: hover every highlighted cell and print each unique
+    /// tooltip. With `all`, walk every occupied quad tab cell instead (see
+    /// `occupied_grid_cells`), hovering each item once.
+    fn stash_copy(&self, grid: &TabGrid, all: bool) -> anyhow::Result<()> {
+        if all {
+            return self.stash_copy_all(grid);
+        }
         self.focus_game_window()?;
 
-        let settings = self.settings.read();
-        let grid = match &settings.stash_grid {
-            Some(g) => g.clone(),
-            None => bail!("Stash grid not calibrated — run: little_oil calibrate-stash"),
+        let (frame, matcher) = {
+            let s = self.settings.read();
+            (s.screenshot()?, s.colour_matcher())
         };
-        let frame = settings.screenshot()?;
-        drop(settings);
 
         let mut seen: Vec<String> = Vec::new();
         let mut failed = 0u32;
 
-        for row in 0..QUAD_ROWS {
-            for col in 0..QUAD_COLS {
-                if !grid.is_highlighted(&frame, col, row) {
+        let (cols, rows) = grid.dims();
+        for row in 0..rows {
+            for col in 0..cols {
+                if !grid.is_highlighted(&frame, col, row, matcher) {
                     continue;
                 }
-                let (px, py) = grid.cell_center(col, row);
+                let Some((px, py)) = grid.cell_center(col, row) else {
+                    continue;
+                };
                 let (sx, sy) = frame.frame_to_screen(px, py);
                 self.move_mouse(sx, sy);
                 std::thread::sleep(std::time::Duration::from_millis(30));
@@ -584,8 +904,49 @@ impl App {
                 }
             }
         }
+        Self::print_copied(&seen, failed);
+        Ok(())
+    }
 
-        for item in &seen {
+    fn stash_copy_all(&self, grid: &TabGrid) -> anyhow::Result<()> {
+        let TabGrid::Quad(grid) = grid else {
+            bail!(
+                "stash copy --all needs a quad tab — empty-cell colors are only sampled for the quad grid"
+            );
+        };
+        let expected = self.settings.read().stash_empty_samples()?;
+        self.focus_game_window()?;
+        self.park_cursor()?;
+        let frame = self.settings.read().screenshot()?;
+
+        let cells: Vec<(u32, u32)> = Self::occupied_grid_cells(&frame, grid, &expected)
+            .into_iter()
+            .map(|(col, row)| (col as u32, row as u32))
+            .collect();
+        let dims = (QUAD_COLS as u32, QUAD_ROWS as u32);
+        let items = crate::inventory::merge(&cells, dims, |col, row| {
+            let (px, py) = grid.cell_center(col as usize, row as usize);
+            let (sx, sy) = frame.frame_to_screen(px, py);
+            self.move_mouse(sx, sy);
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            self.try_read_item_on_cursor()
+        });
+
+        let mut seen: Vec<String> = Vec::new();
+        let mut failed = 0u32;
+        for item in items {
+            match item.text {
+                Some(text) if !seen.contains(&text) => seen.push(text),
+                Some(_) => {}
+                None => failed += 1,
+            }
+        }
+        Self::print_copied(&seen, failed);
+        Ok(())
+    }
+
+    fn print_copied(seen: &[String], failed: u32) {
+        for item in seen {
             println!("{item}");
             println!("--------");
         }
@@ -594,7 +955,254 @@ impl App {
             seen.len(),
             failed
         );
+    }
+
+    /// Divination card turn-in: with the Divination Card tab and the card
+    /// trade window open, hover every card slot of the tab (`div-tab` box
+    /// split by `div_tab_slots`), and for every full stack (`Stack Size:
+    /// N/N`) ctrl-click it into the trade window, click the calibrated `trade`
+    /// point, then ctrl-click the reward at `div-reward` into the inventory.
+    /// Every hover and click is paced by `div_delay`. Stops once the
+    /// inventory reads full.
+    fn div_turnin(&self) -> anyhow::Result<()> {
+        self.focus_game_window()?;
+        let snapshot = { self.settings.read().clone() };
+        let inv_region = snapshot.inv_region.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Inventory region not calibrated — run: little_oil set-region inventory"
+            )
+        })?;
+        let expected = snapshot.inv_empty_samples()?;
+        let tab = self.named_region("div-tab")?;
+        let Some(slots) = snapshot.div_tab_slots else {
+            bail!(
+                "Divination tab layout not set — run: little_oil calibrate-div <cols> <rows> (card slots per page)"
+            );
+        };
+        let trade = self.named_point("trade")?;
+        let reward = self.named_point("div-reward")?;
+        let delay = std::time::Duration::from_millis(snapshot.div_delay);
+        let inventory_full = || -> anyhow::Result<bool> {
+            self.park_cursor()?;
+            let frame = snapshot.screenshot()?;
+            let locked = snapshot.inv_locked().iter().filter(|l| **l).count();
+            let occupied =
+                Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+            Ok(occupied.len() + locked >= 60)
+        };
+
+        let mut traded = 0u32;
+        let mut partial = 0u32;
+        for (sx, sy) in Self::div_slot_centers(tab, slots) {
+            self.move_mouse(sx, sy);
+            std::thread::sleep(delay);
+            let Some(text) = self.try_read_item_on_cursor() else {
+                continue;
+            };
+            if !text.contains("Item Class: Divination Cards") {
+                continue;
+            }
+            match crate::item::stack_size(&text) {
+                Some((have, max)) if have == max => {}
+                _ => {
+                    partial += 1;
+                    continue;
+                }
+            }
+            if inventory_full()? {
+                println!("Inventory full — stopping");
+                break;
+            }
+
+            self.ctrl_click(sx, sy);
+            std::thread::sleep(delay);
+            self.click(trade.0, trade.1);
+            std::thread::sleep(delay);
+            self.ctrl_click(reward.0, reward.1);
+            std::thread::sleep(delay);
+            traded += 1;
+        }
+
+        println!("Traded {traded} full stack(s), skipped {partial} partial stack(s)");
+        Ok(())
+    }
+
+    /// Centres of a `[cols, rows]` grid of equal slots filling `tab`, row by
+    /// row.
+    fn div_slot_centers(tab: ScreenRegion, [cols, rows]: [u32; 2]) -> Vec<(i32, i32)> {
+        let (w, h) = (tab.width / cols.max(1), tab.height / rows.max(1));
+        (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                (
+                    (tab.x + col * w + w / 2) as i32,
+                    (tab.y + row * h + h / 2) as i32,
+                )
+            })
+            .collect()
+    }
+
+    /// `vend`: sell the whole inventory to a vendor.
+    ///
+    /// Clicks the `vendor` NPC and the `vendor-sell` dialog option, merges the
+    /// occupied, unlocked inventory cells into items (see `read_inv_items`,
+    /// read before Ctrl goes down), ctrl-clicks each item once into the sell
+    /// window, and only clicks `vendor-accept` once the `vendor-offer` box (the
+    /// vendor's side of the window, slurped with calibrate-point) changed on
+    /// screen — an empty offer means the items never arrived and nothing is
+    /// accepted. Returns the number of items clicked.
+    pub(crate) fn vend(&self) -> anyhow::Result<usize> {
+        self.focus_game_window()?;
+        let snapshot = { self.settings.read().clone() };
+        let inv_region = snapshot.inv_region.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Inventory region not calibrated — run: little_oil set-region inventory"
+            )
+        })?;
+        let expected = snapshot.inv_empty_samples()?;
+        let vendor = self.named_point("vendor")?;
+        let sell = self.named_point("vendor-sell")?;
+        let accept = self.named_point("vendor-accept")?;
+        let offer = self.named_region("vendor-offer")?;
+        let delay = std::time::Duration::from_millis(snapshot.pull_delay);
+
+        self.click(vendor.0, vendor.1);
+        // Walking up to the NPC and opening the dialog takes a moment.
+        std::thread::sleep(delay + std::time::Duration::from_millis(800));
+        self.click(sell.0, sell.1);
+        std::thread::sleep(delay + std::time::Duration::from_millis(300));
+
+        self.park_cursor()?;
+        let frame = snapshot.screenshot()?;
+        let cells = Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+        let targets: Vec<(i32, i32)> = self
+            .read_inv_items(inv_region, &cells, &[])
+            .iter()
+            .map(|item| Self::inv_item_center(inv_region, item))
+            .collect();
+        if targets.is_empty() {
+            println!("Nothing to sell");
+            return Ok(0);
+        }
 
+        // Hovering left a tooltip up; the offer baseline is taken without it.
+        self.park_cursor()?;
+        let before = snapshot.screenshot()?;
+        self.input.lock().key(InputKey::Ctrl, true);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        for (sx, sy) in &targets {
+            self.click_fast(*sx, *sy);
+            std::thread::sleep(delay);
+        }
+        self.input.lock().key(InputKey::Ctrl, false);
+
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        self.park_cursor()?;
+        let after = snapshot.screenshot()?;
+        if !Self::region_changed(&before, &after, offer, snapshot.colour_matcher())? {
+            bail!(
+                "Vendor offer is still empty after {} click(s) — not accepting; check the sell window and the vendor-offer point",
+                targets.len()
+            );
+        }
+        self.click(accept.0, accept.1);
+        std::thread::sleep(delay);
+        println!("Sold {} inventory item(s)", targets.len());
+        Ok(targets.len())
+    }
+
+    /// Whether anything inside the screen-space `region` differs between two
+    /// frames. A region off the captured frame never counts as changed.
+    fn region_changed(
+        before: &ScreenshotData,
+        after: &ScreenshotData,
+        region: ScreenRegion,
+        matcher: crate::colour::Matcher,
+    ) -> anyhow::Result<bool> {
+        let Some((x, y)) = before.screen_to_frame(region.x, region.y) else {
+            return Ok(false);
+        };
+        let bounds = Rect {
+            x,
+            y,
+            width: region.width,
+            height: region.height,
+        };
+        Ok(!crate::screenshot::diff_clusters(before, after, bounds, 20, matcher)?.is_empty())
+    }
+
+    /// Identify every unidentified inventory item with the Scroll of Wisdom
+    /// stack at the `wisdom` point (slurped first if not yet calibrated).
+    ///
+    /// Tooltips are read before Shift goes down — Ctrl+Alt+C with Shift held
+    /// is a different chord — and deduplicated by text like `stash_copy`, so a
+    /// multi-cell item is clicked once. Then the scroll is right-clicked once,
+    /// Shift is held for the whole pass, and only cells whose tooltip says
+    /// "Unidentified" are left-clicked.
+    fn identify(&self) -> anyhow::Result<()> {
+        let has_wisdom = {
+            let settings = self.settings.read();
+            settings
+                .points
+                .as_ref()
+                .is_some_and(|ps| ps.iter().any(|p| p.name == "wisdom"))
+        };
+        if !has_wisdom {
+            prompt_enter("No 'wisdom' point yet — next, slurp the Scroll of Wisdom stack.")?;
+            self.calibrate_point("wisdom")?;
+        }
+        let wisdom = self.named_point("wisdom")?;
+
+        self.focus_game_window()?;
+        let snapshot = { self.settings.read().clone() };
+        let inv_region = snapshot.inv_region.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Inventory region not calibrated — run: little_oil set-region inventory"
+            )
+        })?;
+        let expected = snapshot.inv_empty_samples()?;
+
+        self.park_cursor()?;
+        let frame = snapshot.screenshot()?;
+        let cells = Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+
+        let targets: Vec<(i32, i32)> = self
+            .read_inv_items(inv_region, &cells, &[])
+            .iter()
+            .filter(|item| {
+                item.text
+                    .as_deref()
+                    .is_some_and(|t| t.contains("\nUnidentified"))
+            })
+            .map(|item| Self::inv_item_center(inv_region, item))
+            .collect();
+        if targets.is_empty() {
+            println!("Nothing to identify");
+            return Ok(());
+        }
+
+        self.click_right(wisdom.0, wisdom.1);
+        std::thread::sleep(std::time::Duration::from_millis(snapshot.pull_delay));
+        self.input.lock().key(InputKey::Shift, true);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        for (sx, sy) in &targets {
+            self.click(*sx, *sy);
+            std::thread::sleep(std::time::Duration::from_millis(snapshot.pull_delay));
+        }
+        self.input.lock().key(InputKey::Shift, false);
+
+        println!("Identified {} item(s):", targets.len());
+        for (sx, sy) in &targets {
+            self.move_mouse(*sx, *sy);
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            match self.try_read_item_on_cursor() {
+                Some(text) if text.contains("\nUnidentified") => {
+                    println!("  ({sx}, {sy}) still unidentified — out of scrolls?")
+                }
+                Some(text) => println!("  {}", crate::item::display_name(&text)),
+                None => println!("  ({sx}, {sy}) could not be read"),
+            }
+        }
         Ok(())
     }
 
@@ -646,6 +1254,21 @@ impl App {
         ])
     }
 
+    /// Mean colour and spread of a probe pixel and its 4 neighbours across
+    /// `frames` (see `colour::stats`).
+    fn sample_probe(frames: &[ScreenshotData], (px, py): (usize, usize)) -> (u32, u8) {
+        let reads: Vec<u32> = frames
+            .iter()
+            .flat_map(|f| {
+                [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)].map(|(ox, oy): (isize, isize)| {
+                    f.try_get_pixel(px.wrapping_add_signed(ox), py.wrapping_add_signed(oy))
+                })
+            })
+            .flatten()
+            .collect();
+        crate::colour::stats(&reads)
+    }
+
     pub(crate) fn reset_inv_colors(&self) -> anyhow::Result<()> {
         let settings = self.settings.read();
         let inv_region = settings.inv_region.ok_or_else(|| {
@@ -654,25 +1277,33 @@ impl App {
             )
         })?;
 
-        let frame = settings.screenshot()?;
+        // Several frames, and each probe's 4 neighbours: the spread of those
+        // reads is the capture noise detection has to tolerate.
+        let mut frames = Vec::new();
+        for _ in 0..INV_SAMPLE_FRAMES {
+            frames.push(settings.screenshot()?);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
         drop(settings);
 
         let mut samples = vec![[0u32; 3]; 60];
+        let mut spread = vec![[0u8; 3]; 60];
 
         for x in 0..12 {
             for y in 0..5 {
-                let probes = Self::inv_probes(&frame, inv_region, x, y).ok_or_else(|| {
+                let probes = Self::inv_probes(&frames[0], inv_region, x, y).ok_or_else(|| {
                     anyhow::anyhow!(
                         "Inventory slot ({x}, {y}) falls outside the game window region — re-run set-region window and set-region inventory"
                     )
                 })?;
-                samples[(x * 5 + y) as usize] = [
-                    frame.try_get_pixel(probes[0].0, probes[0].1).unwrap_or(0),
-                    frame.try_get_pixel(probes[1].0, probes[1].1).unwrap_or(0),
-                    frame.try_get_pixel(probes[2].0, probes[2].1).unwrap_or(0),
-                ];
+                for (i, &probe) in probes.iter().enumerate() {
+                    let (mean, sd) = Self::sample_probe(&frames, probe);
+                    samples[(x * 5 + y) as usize][i] = mean;
+                    spread[(x * 5 + y) as usize][i] = sd;
+                }
             }
         }
+        let worst = spread.iter().flatten().max().copied().unwrap_or(0);
 
         let mut settings = self.settings.write();
 
@@ -682,7 +1313,7 @@ impl App {
                 "low",
                 "Little Oil",
                 &format!(
-                    "Inventory colors calibrated: {} slots x 3 samples",
+                    "Inventory colors calibrated: {} slots x 3 samples (max spread {worst})",
                     samples.len()
                 ),
             ])
@@ -691,37 +1322,141 @@ impl App {
             eprintln!("notify-send failed: {e}");
         }
         settings.inv_samples = Some(samples);
+        settings.inv_sample_spread = Some(spread);
+
+        save_config(&config_path()?, &settings.to_disk()?)?;
+        Ok(())
+    }
+
+    /// Sample every cell of an empty, unsearched grid (see
+    /// `CellGrid::interior_probes`) as its empty-cell colours, column-major
+    /// (index = col * R + row).
+    fn sample_empty_grid<const C: usize, const R: usize>(
+        &self,
+        grid: &CellGrid<C, R>,
+        recalibrate: &str,
+    ) -> anyhow::Result<crate::colour::ProbeSamples> {
+        self.park_cursor()?;
+        let mut frames = Vec::new();
+        let matcher = {
+            let settings = self.settings.read();
+            for _ in 0..INV_SAMPLE_FRAMES {
+                frames.push(settings.screenshot()?);
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            settings.colour_matcher()
+        };
+
+        let mut samples = vec![[0u32; 3]; C * R];
+        let mut spread = vec![[0u8; 3]; C * R];
+        for col in 0..C {
+            for row in 0..R {
+                for (i, probe) in grid.interior_probes(col, row).into_iter().enumerate() {
+                    if frames[0].try_get_pixel(probe.0, probe.1).is_none() {
+                        bail!(
+                            "Cell ({col}, {row}) falls outside the game window region — re-run {recalibrate}"
+                        );
+                    }
+                    let (mean, sd) = Self::sample_probe(&frames, probe);
+                    samples[col * R + row][i] = mean;
+                    spread[col * R + row][i] = sd;
+                }
+            }
+        }
+        Ok(crate::colour::ProbeSamples {
+            colours: samples,
+            spread,
+            matcher,
+        })
+    }
+
+    /// `reset_stash`: sample every cell of an empty quad tab (open and
+    /// showing, nothing searched) as the empty-cell colours for
+    /// `occupied_grid_cells`.
+    pub(crate) fn reset_stash_colors(&self) -> anyhow::Result<()> {
+        let grid = self.settings.read().stash_grid.clone().ok_or_else(|| {
+            anyhow::anyhow!("Stash grid not calibrated — run: little_oil calibrate-stash")
+        })?;
+        let sampled = self.sample_empty_grid(&grid, "calibrate-stash")?;
+        let worst = sampled.spread.iter().flatten().max().copied().unwrap_or(0);
 
-        save_config(&config_path()?, &*settings)?;
+        let mut settings = self.settings.write();
+        settings.stash_samples = Some(sampled.colours);
+        settings.stash_sample_spread = Some(sampled.spread);
+        save_config(&config_path()?, &settings.to_disk()?)?;
+        println!(
+            "Quad tab colors calibrated: {} cells x 3 samples (max spread {worst})",
+            QUAD_COLS * QUAD_ROWS
+        );
         Ok(())
     }
 
+    /// `reset_map`: sample every cell of an empty map tab grid, so `load-map`
+    /// can find a map without a search.
+    pub(crate) fn reset_map_colors(&self) -> anyhow::Result<()> {
+        let grid = self.settings.read().map_grid.clone().ok_or_else(|| {
+            anyhow::anyhow!("Map grid not calibrated — run: little_oil calibrate-map")
+        })?;
+        let sampled = self.sample_empty_grid(&grid, "calibrate-map")?;
+        let worst = sampled.spread.iter().flatten().max().copied().unwrap_or(0);
+
+        let mut settings = self.settings.write();
+        settings.map_samples = Some(sampled.colours);
+        settings.map_sample_spread = Some(sampled.spread);
+        save_config(&config_path()?, &settings.to_disk()?)?;
+        println!(
+            "Map tab colors calibrated: {} cells x 3 samples (max spread {worst})",
+            MAP_COLS * MAP_ROWS
+        );
+        Ok(())
+    }
+
+    /// Occupied cells of a grid in `frame` — fewer than 2 of 3 interior
+    /// probes matching the calibrated empty-cell sample — as (col, row),
+    /// column-major.
+    pub(crate) fn occupied_grid_cells<const C: usize, const R: usize>(
+        frame: &ScreenshotData,
+        grid: &CellGrid<C, R>,
+        expected: &crate::colour::ProbeSamples,
+    ) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for col in 0..C {
+            for row in 0..R {
+                let reads = grid
+                    .interior_probes(col, row)
+                    .map(|(px, py)| frame.try_get_pixel(px, py));
+                if expected.matching(col * R + row, reads) < 2 {
+                    cells.push((col, row));
+                }
+            }
+        }
+        cells
+    }
+
     /// Occupied inventory cells in `frame` — fewer than 2 of 3 probe pixels
-    /// matching the calibrated empty-slot sample — as screen coordinates.
+    /// matching the calibrated empty-slot sample within tolerance — as
+    /// screen coordinates.
+    /// Cells set in `locked` (see `Settings::inv_locked`) are never reported,
+    /// so no macro built on this can click them.
     pub(crate) fn occupied_inv_cells(
         frame: &ScreenshotData,
         inv_region: ScreenRegion,
-        expected: &[[u32; 3]],
+        expected: &crate::colour::ProbeSamples,
+        locked: &[bool],
     ) -> anyhow::Result<Vec<(i32, i32)>> {
         let mut cells = Vec::new();
         for x in 0..12 {
             for y in 0..5 {
+                if locked.get((x * 5 + y) as usize) == Some(&true) {
+                    continue;
+                }
                 let probes = Self::inv_probes(frame, inv_region, x, y).ok_or_else(|| {
                     anyhow::anyhow!(
                         "Inventory slot ({x}, {y}) falls outside the game window region — re-run set-region window and set-region inventory"
                     )
                 })?;
-                let actual = [
-                    frame.try_get_pixel(probes[0].0, probes[0].1).unwrap_or(0),
-                    frame.try_get_pixel(probes[1].0, probes[1].1).unwrap_or(0),
-                    frame.try_get_pixel(probes[2].0, probes[2].1).unwrap_or(0),
-                ];
-                let stored = expected[(x * 5 + y) as usize];
-                let matches = actual
-                    .iter()
-                    .zip(stored.iter())
-                    .filter(|(a, e)| a == e)
-                    .count();
+                let actual = probes.map(|(px, py)| frame.try_get_pixel(px, py));
+                let matches = expected.matching((x * 5 + y) as usize, actual);
 
                 if matches < 2 {
                     debug!(x, y, "clicking inv");
@@ -734,10 +1469,12 @@ impl App {
         Ok(cells)
     }
 
-    /// Empty the inventory: screenshot, click every occupied cell fast, then
-    /// re-screenshot and repeat so clicks the game missed get retried. Up to 3
-    /// passes; Ctrl is held for the whole pass so every click is a move.
-    /// Returns (items clicked, cells still occupied after the last pass).
+    /// Empty the inventory: screenshot, merge the occupied cells into items
+    /// (see `read_inv_items`), click each item once fast, then re-screenshot
+    /// and repeat so clicks the game missed get retried. Up to 3 passes; Ctrl
+    /// is held for the clicks so every click is a move. Footprints are read
+    /// once: a retry pass only hovers cells whose occupancy changed. Returns
+    /// (items clicked, cells still occupied after the last pass).
     fn empty_inv_macro(&self, clicker: fn(&App, i32, i32)) -> anyhow::Result<(u32, u32)> {
         let settings = self.settings.read();
         let inv_region = settings.inv_region.ok_or_else(|| {
@@ -748,20 +1485,19 @@ impl App {
 
         info!("Emptying inv");
 
-        let expected = match settings.inv_samples.as_ref() {
-            Some(s) if s.len() == 60 => s,
-            _ => bail!("Inventory colors not calibrated — run: little_oil reset_inv"),
-        };
+        let expected = settings.inv_empty_samples()?;
 
         let mut clicked: u32 = 0;
         let mut remaining: u32 = 0;
         let mut prev_occupied: u32 = 0;
+        let mut items: Vec<InvItem> = Vec::new();
         for pass in 0..3 {
             // Park the cursor off any item and wait a frame, so no slot is
             // hovered (and no highlight pollutes the probe pixels).
             self.park_cursor()?;
             let frame = settings.screenshot()?;
-            let mut cells = Self::occupied_inv_cells(&frame, inv_region, expected)?;
+            let cells =
+                Self::occupied_inv_cells(&frame, inv_region, &expected, settings.inv_locked())?;
             let occupied = cells.len() as u32;
             info!(pass = pass + 1, occupied, first_cell = ?cells.first(), "empty pass");
             if occupied == 0 {
@@ -777,7 +1513,7 @@ impl App {
                     }
                     if let Some(probes) = Self::inv_probes(&frame, inv_region, 0, 0) {
                         info!(
-                            sample = ?expected[0],
+                            sample = ?expected.colours[0],
                             probe = ?frame.try_get_pixel(probes[1].0, probes[1].1),
                             "pass-1 cell (0,0) probe vs sample"
                         );
@@ -785,7 +1521,8 @@ impl App {
                 }
                 break;
             }
-            if occupied == 60 && pass == 0 {
+            let locked = settings.inv_locked().iter().filter(|l| **l).count() as u32;
+            if occupied + locked == 60 && pass == 0 {
                 // The whole inventory reads occupied — correct, or the capture
                 // is off (wrong region, black frame from direct scanout).
                 let path = std::path::Path::new("/tmp/little_oil_empty_pass1.png");
@@ -802,13 +1539,18 @@ impl App {
             prev_occupied = occupied;
             remaining = occupied;
 
-            // Click the occupied cells in a random order so the macro never
-            // produces the same fixed scan pattern twice.
-            cells.shuffle(&mut rand::rng());
+            // Click the items in a random order so the macro never produces
+            // the same fixed scan pattern twice.
+            items = self.read_inv_items(inv_region, &cells, &items);
+            let mut targets: Vec<(i32, i32)> = items
+                .iter()
+                .map(|item| Self::inv_item_center(inv_region, item))
+                .collect();
+            targets.shuffle(&mut rand::rng());
 
             self.input.lock().key(InputKey::Ctrl, true);
             std::thread::sleep(std::time::Duration::from_millis(5));
-            for (sx, sy) in &cells {
+            for (sx, sy) in &targets {
                 clicker(self, *sx, *sy);
                 clicked += 1;
                 std::thread::sleep(std::time::Duration::from_millis(10));
@@ -826,12 +1568,132 @@ impl App {
             // clicked slot.
             self.park_cursor()?;
             let frame = settings.screenshot()?;
-            remaining = Self::occupied_inv_cells(&frame, inv_region, expected)?.len() as u32;
+            remaining =
+                Self::occupied_inv_cells(&frame, inv_region, &expected, settings.inv_locked())?
+                    .len() as u32;
         }
 
         Ok((clicked, remaining))
     }
 
+    /// Inventory (col, row) of a screen point inside `inv_region`.
+    pub(crate) fn inv_cell_of(inv_region: ScreenRegion, sx: i32, sy: i32) -> (u32, u32) {
+        let dx = (inv_region.width / 12).max(1) as i32;
+        let dy = (inv_region.height / 5).max(1) as i32;
+        (
+            ((sx - inv_region.x as i32) / dx).clamp(0, 11) as u32,
+            ((sy - inv_region.y as i32) / dy).clamp(0, 4) as u32,
+        )
+    }
+
+    /// Screen point in the middle of `w` x `h` inventory cells starting at
+    /// (col, row).
+    fn inv_box_center(inv_region: ScreenRegion, col: u32, row: u32, w: u32, h: u32) -> (i32, i32) {
+        let dx = inv_region.width / 12;
+        let dy = inv_region.height / 5;
+        (
+            (inv_region.x + col * dx + w * dx / 2) as i32,
+            (inv_region.y + row * dy + h * dy / 2) as i32,
+        )
+    }
+
+    /// Screen point in the middle of an item's footprint.
+    pub(crate) fn inv_item_center(inv_region: ScreenRegion, item: &InvItem) -> (i32, i32) {
+        Self::inv_box_center(inv_region, item.col, item.row, item.w, item.h)
+    }
+
+    /// Hover the occupied inventory cells and merge them into items (see
+    /// `inventory::merge`): one hover per item when its footprint is known,
+    /// one per cell otherwise. Items in `known` (from an earlier read) whose
+    /// cells are all still occupied are kept without hovering. The result is
+    /// also kept for the GUI overlay.
+    fn read_inv_items(
+        &self,
+        inv_region: ScreenRegion,
+        cells: &[(i32, i32)],
+        known: &[InvItem],
+    ) -> Vec<InvItem> {
+        let cells: Vec<(u32, u32)> = cells
+            .iter()
+            .map(|&(sx, sy)| Self::inv_cell_of(inv_region, sx, sy))
+            .collect();
+        let (mut items, changed) = crate::inventory::carry_over(known, &cells);
+        let dims = (crate::inventory::INV_COLS, crate::inventory::INV_ROWS);
+        items.extend(crate::inventory::merge(&changed, dims, |col, row| {
+            let (sx, sy) = Self::inv_box_center(inv_region, col, row, 1, 1);
+            self.move_mouse(sx, sy);
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            self.try_read_item_on_cursor()
+        }));
+        *self.inv_items.lock() = items.clone();
+        items
+    }
+
+    /// `empty --filter <file>`: read every inventory item and ctrl-click
+    /// only those the rule file matches (see `item_rules`). Everything else
+    /// stays put and is listed in the summary, including items whose tooltip
+    /// could not be read.
+    fn empty_inv_filtered(&self, rules_path: &str) -> anyhow::Result<()> {
+        let rules: crate::item_rules::ItemRules = crate::load_config(Path::new(rules_path), None)?;
+        self.focus_game_window()?;
+        let snapshot = { self.settings.read().clone() };
+        let inv_region = snapshot.inv_region.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Inventory region not calibrated — run: little_oil set-region inventory"
+            )
+        })?;
+        let expected = snapshot.inv_empty_samples()?;
+
+        self.park_cursor()?;
+        let frame = snapshot.screenshot()?;
+        let cells = Self::occupied_inv_cells(&frame, inv_region, &expected, snapshot.inv_locked())?;
+        let items = self.read_inv_items(inv_region, &cells, &[]);
+
+        let mut moved = Vec::new();
+        let mut kept = Vec::new();
+        let mut unread = Vec::new();
+        for item in &items {
+            let Some(text) = item.text.as_deref() else {
+                unread.push((item.col, item.row));
+                continue;
+            };
+            let hit =
+                crate::item::Item::from_str(text).is_ok_and(|parsed| rules.matches(&parsed, text));
+            if hit {
+                moved.push((
+                    crate::item::display_name(text),
+                    Self::inv_item_center(inv_region, item),
+                ));
+            } else {
+                kept.push(crate::item::display_name(text));
+            }
+        }
+
+        self.input.lock().key(InputKey::Ctrl, true);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        for (_, (sx, sy)) in &moved {
+            self.click_fast(*sx, *sy);
+            std::thread::sleep(std::time::Duration::from_millis(snapshot.pull_delay));
+        }
+        self.input.lock().key(InputKey::Ctrl, false);
+
+        println!("Moved {} item(s):", moved.len());
+        for (name, _) in &moved {
+            println!("  {name}");
+        }
+        println!("Kept {} item(s):", kept.len());
+        for name in &kept {
+            println!("  {name}");
+        }
+        if !unread.is_empty() {
+            println!("Kept {} unread item(s):", unread.len());
+            for (col, row) in &unread {
+                println!("  unread at ({col}, {row})");
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn empty_inv(&self) -> anyhow::Result<()> {
         self.empty_inv_with(App::click_fast)
     }
@@ -864,27 +1726,26 @@ impl App {
         Ok(())
     }
 
-    fn sort_quad(&self, times: u32) -> anyhow::Result<()> {
+    fn sort_tab(&self, times: u32, grid: &TabGrid) -> anyhow::Result<()> {
         self.focus_game_window()?;
         std::thread::sleep(std::time::Duration::from_millis(300));
 
         let settings = self.settings.read();
         let delay = settings.pull_delay;
-        let grid = match &settings.stash_grid {
-            Some(g) => g.clone(),
-            None => bail!("Stash grid not calibrated — run: little_oil calibrate-stash"),
-        };
         let frame = settings.screenshot()?;
+        let matcher = settings.colour_matcher();
         drop(settings);
 
         let mut movesleft = times;
-        for row in 0..QUAD_ROWS {
-            for col in 0..QUAD_COLS {
+        let (cols, rows) = grid.dims();
+        for row in 0..rows {
+            for col in 0..cols {
                 if movesleft < 1 {
                     return Ok(());
                 }
-                if grid.is_highlighted(&frame, col, row) {
-                    let (px, py) = grid.cell_center(col, row);
+                if grid.is_highlighted(&frame, col, row, matcher)
+                    && let Some((px, py)) = grid.cell_center(col, row)
+                {
                     let (sx, sy) = frame.frame_to_screen(px, py);
                     self.click(sx, sy);
                     std::thread::sleep(std::time::Duration::from_millis(delay));
@@ -933,10 +1794,20 @@ impl App {
                 return Ok(());
             }
             Some("sort") => {
-                let times: u32 = args.get(1).map(|x| x.parse()).transpose()?.unwrap_or(40);
-                return self.sort_quad(times);
+                let times: u32 = args
+                    .get(1)
+                    .filter(|a| !a.starts_with("--"))
+                    .map(|x| x.parse())
+                    .transpose()?
+                    .unwrap_or(40);
+                let grid = self.tab_grid_arg(args)?;
+                return self.sort_tab(times, &grid);
             }
-            Some("empty") => return self.empty_inv(),
+            Some("empty") => match (args.get(1).map(|x| &**x), args.get(2)) {
+                (Some("--filter"), Some(file)) => return self.empty_inv_filtered(file),
+                (Some(_), _) => bail!("Usage: little_oil empty [--filter <rules.json>]"),
+                (None, _) => return self.empty_inv(),
+            },
             Some("emptyr") => return self.empty_inv_right(),
             Some("roll") => {
                 let file = args.get(1).ok_or_else(|| {
@@ -951,6 +1822,40 @@ impl App {
                 return Ok(());
             }
             Some("reset_inv") => return self.reset_inv_colors(),
+            Some("reset_stash" | "reset-stash") => return self.reset_stash_colors(),
+            Some("reset_map") => return self.reset_map_colors(),
+            Some("lock") => {
+                let usage = "Usage: little_oil lock <col> <row>  (cols 0..12, rows 0..5)";
+                match (args.get(1), args.get(2)) {
+                    (Some(col), Some(row)) => {
+                        let (col, row): (u32, u32) = (col.parse()?, row.parse()?);
+                        if col >= 12 || row >= 5 {
+                            bail!("inventory cell ({col}, {row}) out of range — {usage}");
+                        }
+                        let mut settings = self.settings.write();
+                        let locked = settings.toggle_inv_lock(col, row);
+                        save_config(&config_path()?, &settings.to_disk()?)?;
+                        let state = if locked { "locked" } else { "unlocked" };
+                        println!("Inventory cell ({col}, {row}) {state}");
+                    }
+                    _ => println!("{usage}"),
+                }
+                // Show the whole mask either way: # locked, . free.
+                let settings = self.settings.read();
+                for row in 0..5u32 {
+                    let line: String = (0..12u32)
+                        .map(|col| {
+                            if settings.inv_locked().get((col * 5 + row) as usize) == Some(&true) {
+                                '#'
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect();
+                    println!("  {line}");
+                }
+                return Ok(());
+            }
             Some("calibrate-pointer") => {
                 // Platforms with absolute pointer positioning (niri on Linux,
                 // Windows) don't need pointer_scale calibration.
@@ -969,6 +1874,7 @@ impl App {
                 );
             }
             Some("calibrate-stash") => return self.calibrate_stash(),
+            Some("calibrate-normal") => return self.calibrate_normal(),
             Some("calibrate-map") => return self.calibrate_map(),
             Some("calibrate-point") => {
                 let name = args
@@ -976,6 +1882,13 @@ impl App {
                     .ok_or_else(|| anyhow::anyhow!("Usage: little_oil calibrate-point <name>"))?;
                 return self.calibrate_point(name);
             }
+            Some("calibrate-div") => {
+                let usage = "Usage: little_oil calibrate-div <cols> <rows>";
+                let [Some(cols), Some(rows)] = [args.get(1), args.get(2)] else {
+                    bail!("{usage}");
+                };
+                return self.calibrate_div(cols.parse()?, rows.parse()?);
+            }
             Some("calibrate-currency") => {
                 for name in [
                     "transmute",
@@ -996,22 +1909,63 @@ impl App {
                 }
                 return Ok(());
             }
+            Some("load-map") => {
+                // load-map [tier] [col row]: an odd number of arguments leads
+                // with the tier.
+                let rest = &args[1..];
+                let (tier, coords) = if rest.len() % 2 == 1 {
+                    (Some(rest[0].as_str()), &rest[1..])
+                } else {
+                    (None, rest)
+                };
+                let cell = match coords {
+                    [] => None,
+                    [col, row] => {
+                        let (col, row): (usize, usize) = (col.parse()?, row.parse()?);
+                        if col >= MAP_COLS || row >= MAP_ROWS {
+                            bail!(
+                                "map cell ({col}, {row}) out of range — cols 0..{MAP_COLS}, rows 0..{MAP_ROWS}"
+                            );
+                        }
+                        Some((col, row))
+                    }
+                    _ => bail!("Usage: little_oil load-map [tier] [col row]"),
+                };
+                return self.load_map(tier, cell);
+            }
             Some("stash") => {
                 let mode = args.get(1).map(|x| &**x);
                 match mode {
                     Some("click") => {
-                        let times: u32 = args.get(2).map(|x| x.parse()).transpose()?.unwrap_or(40);
-                        return self.sort_quad(times);
+                        let times: u32 = args
+                            .get(2)
+                            .filter(|a| !a.starts_with("--"))
+                            .map(|x| x.parse())
+                            .transpose()?
+                            .unwrap_or(40);
+                        let grid = self.tab_grid_arg(args)?;
+                        return self.sort_tab(times, &grid);
+                    }
+                    Some("copy") => {
+                        let grid = self.tab_grid_arg(args)?;
+                        return self.stash_copy(&grid, args.iter().any(|a| a == "--all"));
                     }
-                    Some("copy") => return self.stash_copy(),
                     _ => {
-                        println!("Usage: little_oil stash <click|copy> [times]");
+                        println!(
+                            "Usage: little_oil stash <click|copy> [times] [--all] [--tab <name>]"
+                        );
                         println!(
                             "  click <times>  Left-click every highlighted cell (hold Ctrl to pull, Shift to identify)"
                         );
                         println!(
                             "  copy           Hover every highlighted cell and Ctrl+Alt+C it, printing unique items"
                         );
+                        println!(
+                            "  --all          With copy: every occupied quad tab cell, one hover per item (needs reset_stash)"
+                        );
+                        println!(
+                            "  --tab <name>   Use the quad or normal grid by that tab's type (default: quad)"
+                        );
                         return Ok(());
                     }
                 }
@@ -1068,7 +2022,7 @@ impl App {
                         let r = platform.select_region(
                             "Select the GAME WINDOW (drag around the entire PoE window)",
                         )?;
-                        self.settings.write().game_window_region = Some(r);
+                        self.settings.write().set_game_window(r);
                         r
                     }
                     "map" => {
@@ -1086,7 +2040,7 @@ impl App {
                 #[cfg(not(target_os = "linux"))]
                 let _ = region;
 
-                save_config(&config_path()?, &*self.settings.read())?;
+                save_config(&config_path()?, &self.settings.read().to_disk()?)?;
                 #[cfg(target_os = "linux")]
                 let _ = Command::new("notify-send")
                     .args([
@@ -1103,6 +2057,12 @@ impl App {
                 return Ok(());
             }
             Some("chance") => return self.chance(),
+            Some("div-turnin") => return self.div_turnin(),
+            Some("identify") => return self.identify(),
+            Some("vend") => {
+                self.vend()?;
+                return Ok(());
+            }
             Some("tally") => {
                 let c = self
                     .settings
@@ -1110,18 +2070,271 @@ impl App {
                     .chaos_recipe_settings
                     .clone()
                     .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
-                chaos_recipe::get_tally(&self, &c)?;
+                if let [extra, ..] =
+                    positionals(args, &["--mode", "--stash-file", "--max-age"], &[])?[..]
+                {
+                    bail!(
+                        "Unexpected argument '{extra}' — usage: little_oil tally [--mode chaos|regal] [stash source]"
+                    );
+                }
+                let mode = chaos_recipe::RecipeMode::from_args(args)?;
+                let source = chaos_recipe::StashSource::from_args(args)?;
+                chaos_recipe::get_tally(&self, &c, &source, mode)?;
+                return Ok(());
+            }
+            Some("value") => {
+                let usage = "Usage: little_oil value <tab> | --file <json|txt> [--prices <file|dir>] [--top <n>]";
+                let rest = positionals(args, &["--file", "--prices", "--top"], &[])?;
+                let flag = |name: &str| {
+                    args.iter()
+                        .position(|a| a == name)
+                        .map(|i| {
+                            args.get(i + 1)
+                                .ok_or_else(|| anyhow::anyhow!("{name} needs a value"))
+                        })
+                        .transpose()
+                };
+                let source = match (flag("--file")?, &rest[..]) {
+                    (Some(file), []) => crate::pricing::ValueSource::File(file.into()),
+                    (None, [tab]) => crate::pricing::ValueSource::Tab(tab.to_string()),
+                    (Some(_), [extra, ..]) | (None, [_, extra, ..]) => {
+                        bail!("Unexpected argument '{extra}' — {usage}")
+                    }
+                    (None, []) => bail!("{usage}"),
+                };
+                let prices = match flag("--prices")? {
+                    Some(p) => p.into(),
+                    None => crate::prices_dir()?,
+                };
+                let top = flag("--top")?.map(|n| n.parse()).transpose()?.unwrap_or(10);
+                let c = self.settings.read().chaos_recipe_settings.clone();
+                crate::pricing::run(c.as_ref(), &source, &prices, top)?;
+                return Ok(());
+            }
+            Some("recipe") => {
+                let usage = "Usage: little_oil recipe <gcp|bauble|chromatic|jeweller> [sets] [--tab <name>]";
+                let (kind, sets) = match positionals(args, &["--tab"], &[])?[..] {
+                    [kind] => (kind, None),
+                    [kind, sets] => (kind, Some(sets)),
+                    [_, _, extra, ..] => bail!("Unexpected argument '{extra}' — {usage}"),
+                    [] => bail!("{usage}"),
+                };
+                let recipe = crate::vendor_recipes::VendorRecipe::from_name(kind)?;
+                let sets: usize = sets.map(|x| x.parse()).transpose()?.unwrap_or(1);
+                let c = self
+                    .settings
+                    .read()
+                    .chaos_recipe_settings
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+                let tab = match args.iter().position(|a| a == "--tab") {
+                    Some(i) => args
+                        .get(i + 1)
+                        .ok_or_else(|| anyhow::anyhow!("--tab needs a value"))?
+                        .clone(),
+                    None => c.primary_tab().to_string(),
+                };
+                crate::vendor_recipes::run(&self, &c, recipe, &tab, sets)?;
+                return Ok(());
+            }
+            Some("snapshot") => {
+                let c = self
+                    .settings
+                    .read()
+                    .chaos_recipe_settings
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+                if args.len() < 2 {
+                    return crate::stash_history::list(c.league());
+                }
+                return crate::stash_history::take(&c, c.league(), &args[1..]);
+            }
+            Some("diff") => {
+                let c = self
+                    .settings
+                    .read()
+                    .chaos_recipe_settings
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+                let usage = "Usage: little_oil diff [from [to]] [--prices <file|dir>]";
+                let rest = positionals(args, &["--prices"], &[])?;
+                if let [_, _, extra, ..] = rest[..] {
+                    bail!("Unexpected argument '{extra}' — {usage}");
+                }
+                let stamps: Vec<u64> = rest
+                    .iter()
+                    .map(|a| {
+                        a.parse()
+                            .map_err(|_| anyhow::anyhow!("Bad snapshot time '{a}' — {usage}"))
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+                let prices_at = args.iter().position(|a| a == "--prices");
+                let prices = match prices_at {
+                    Some(i) => Some(
+                        args.get(i + 1)
+                            .ok_or_else(|| anyhow::anyhow!("--prices needs a value"))?
+                            .into(),
+                    ),
+                    None => Some(crate::prices_dir()?).filter(|d| d.is_dir()),
+                };
+                return crate::stash_history::run_diff(
+                    c.league(),
+                    stamps.first().copied(),
+                    stamps.get(1).copied(),
+                    prices.as_deref(),
+                );
+            }
+            Some("calib") => {
+                let usage = "Usage: little_oil calib <export|import> <file>";
+                let (Some(mode), Some(file)) = (args.get(1), args.get(2)) else {
+                    bail!("{usage}");
+                };
+                match mode.as_str() {
+                    "export" => {
+                        let bundle = self.settings.read().export_bundle()?;
+                        save_config(Path::new(file), &bundle)?;
+                        println!(
+                            "Exported calibration captured at {}x{} to {file}",
+                            bundle.width, bundle.height
+                        );
+                    }
+                    "import" => {
+                        let bundle: crate::calibration::Bundle =
+                            crate::load_config(Path::new(file), None)?;
+                        let window = {
+                            let s = self.settings.read();
+                            self.platform()
+                                .find_game_window()
+                                .or(s.window_anchor)
+                                .or(s.game_window_region)
+                        }
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Game window not found — launch the game or run: little_oil set-region window"
+                            )
+                        })?;
+                        let mut settings = self.settings.write();
+                        let report = settings.import_bundle(&bundle, window)?;
+                        save_config(&config_path()?, &settings.to_disk()?)?;
+                        println!(
+                            "Imported {} entries from a {}x{} bundle onto the {}x{} window",
+                            report.mapped.len(),
+                            bundle.width,
+                            bundle.height,
+                            window.width,
+                            window.height
+                        );
+                        if !report.unmapped.is_empty() {
+                            println!(
+                                "Could not map (window aspect differs): {} — run: little_oil calibrate-point <name>",
+                                report.unmapped.join(", ")
+                            );
+                        }
+                        if !report.resample.is_empty() {
+                            println!(
+                                "Colour samples were taken at {}x{} and do not carry over to another size — re-sample with: {}",
+                                bundle.width,
+                                bundle.height,
+                                report.resample.join(", ")
+                            );
+                        }
+                    }
+                    _ => bail!("{usage}"),
+                }
+                return Ok(());
+            }
+            Some("profile") => {
+                let usage = "Usage: little_oil profile <list | use <name> | save <name> | copy <from> <to> | delete <name>>";
+                let name = |i: usize| {
+                    args.get(i)
+                        .map(|x| x.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("{usage}"))
+                };
+                let mut settings = self.settings.write();
+                match args.get(1).map(|x| &**x) {
+                    Some("list") | None => {
+                        crate::profiles::list(&settings);
+                        return Ok(());
+                    }
+                    Some("use") => {
+                        if let Some(kept) = settings.use_profile(name(2)?)? {
+                            println!("The unsaved calibration was kept as profile '{kept}'");
+                        }
+                    }
+                    Some("save") => settings.save_profile(name(2)?)?,
+                    Some("copy") => settings.copy_profile(name(2)?, name(3)?)?,
+                    Some("delete") => settings.delete_profile(name(2)?)?,
+                    Some(_) => bail!("{usage}"),
+                }
+                save_config(&config_path()?, &settings.to_disk()?)?;
+                crate::profiles::list(&settings);
+                return Ok(());
+            }
+            Some("tabs") => {
+                let c = self
+                    .settings
+                    .read()
+                    .chaos_recipe_settings
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+                chaos_recipe::list_tabs(&self, &c, &args[1..])?;
+                return Ok(());
+            }
+            Some("filter") => {
+                let usage = "Usage: little_oil filter <file.filter> <sets> [--mode chaos|regal] [--watch <secs>] [--stash-file <json> | --max-age <secs>]";
+                let (file, target) = match positionals(
+                    args,
+                    &["--mode", "--watch", "--stash-file", "--max-age"],
+                    &[],
+                )?[..]
+                {
+                    [file, target] => (file, target),
+                    [_, _, extra, ..] => bail!("Unexpected argument '{extra}' — {usage}"),
+                    _ => bail!("{usage}"),
+                };
+                let target: usize = target.parse()?;
+                let mode = chaos_recipe::RecipeMode::from_args(args)?;
+                let watch = match args.iter().position(|a| a == "--watch") {
+                    Some(i) => Some(
+                        args.get(i + 1)
+                            .ok_or_else(|| anyhow::anyhow!("--watch takes a number of seconds"))?
+                            .parse::<u64>()?,
+                    ),
+                    None => None,
+                };
+                let c = self
+                    .settings
+                    .read()
+                    .chaos_recipe_settings
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+                let source = chaos_recipe::StashSource::from_args(args)?;
+                loot_filter::run(&self, &c, &source, Path::new(file), target, mode, watch)?;
                 return Ok(());
             }
             Some("chaos") => {
-                let amt: usize = args.get(1).map(|x| x.parse()).transpose()?.unwrap_or(1);
+                let mode = chaos_recipe::RecipeMode::from_args(args)?;
+                let amt: usize = match positionals(
+                    args,
+                    &["--mode", "--stash-file", "--max-age"],
+                    &["--vend"],
+                )?[..]
+                {
+                    [] => 1,
+                    [n] => n.parse()?,
+                    [_, extra, ..] => bail!(
+                        "Unexpected argument '{extra}' — usage: little_oil chaos [sets] [--mode chaos|regal] [--vend] [stash source]"
+                    ),
+                };
                 let c = self
                     .settings
                     .read()
                     .chaos_recipe_settings
                     .clone()
                     .ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
-                chaos_recipe::do_recipe(&self, &c, amt)?;
+                let source = chaos_recipe::StashSource::from_args(args)?;
+                let vend = args.iter().any(|a| a == "--vend");
+                chaos_recipe::do_recipe(&self, &c, &source, amt, mode, vend)?;
                 return Ok(());
             }
             Some(n) => {
@@ -1140,6 +2353,34 @@ impl App {
     }
 }
 
+/// The arguments after the command that are neither one of the known
+/// `--flags` nor the value following one of `value_flags`. Any other
+/// `--flag` is an error, so a typo isn't silently ignored.
+fn positionals<'a>(
+    args: &'a [String],
+    value_flags: &[&str],
+    bare_flags: &[&str],
+) -> anyhow::Result<Vec<&'a str>> {
+    let mut out = Vec::new();
+    let mut rest = args.iter().skip(1);
+    while let Some(a) = rest.next() {
+        if value_flags.contains(&a.as_str()) {
+            rest.next();
+        } else if a.starts_with("--") {
+            if !bare_flags.contains(&a.as_str()) {
+                let known = value_flags.iter().chain(bare_flags).copied();
+                bail!(
+                    "Unknown flag '{a}' — expected one of: {}",
+                    known.collect::<Vec<_>>().join(", ")
+                );
+            }
+        } else {
+            out.push(a.as_str());
+        }
+    }
+    Ok(out)
+}
+
 fn split_space(input: &str) -> (&str, &str) {
     for (i, _c) in input.char_indices() {
         if input.as_bytes()[i] == b' ' {
@@ -1164,18 +2405,40 @@ const HELP: &str = r#"
 help: Show this menu
 version: Print version and exit
 empty: Empty the inventory into the stash (ctrl+left click)
+empty --filter <file>: Read each inventory item and move only those matching the rule file
 emptyr: Empty the inventory into the stash (ctrl+right click)
+lock <col> <row>: Toggle an inventory cell the macros must never click
 set-region <inventory|stash|window|map>: Select and save a screen region
 calibrate-pointer: Measure pointer scale (run once per machine)
 calibrate-stash: Calibrate the 24x24 quad tab grid (3 positions, base + search capture each)
+calibrate-normal: Calibrate the 12x12 normal tab grid (3 positions, base + search capture each)
 calibrate-map: Calibrate the 12x7 map tab grid (3 positions, base + search capture each)
 calibrate-point <name>: Slurp a small box and save it as a named clickable point
+calibrate-div <cols> <rows>: Slurp the card slots of one Divination Card tab page (div-tab box) and save its slot count for div-turnin
 calibrate-currency: Calibrate the 10 currency slots (transmute, alt, annul, chance, augment, regal, chaos, scour, alchemy, exalt)
 click <name>: Click a calibrated point (e.g. filter, chaos)
 click map <col> <row>: Click a cell in the calibrated map grid
-stash <click|copy> [times]: Act on highlighted quad-tab cells
+load-map [tier] [col row]: Pull a map (first highlighted, else first occupied, or col/row) and load it plus map_device_points into the map device (points map-device, map-device-window box; map region must be set)
+reset_map: Sample the empty-cell colors of an open, empty map tab (lets load-map find maps without a search)
+stash <click|copy> [times] [--tab <name>]: Act on highlighted cells, on the quad or normal grid by the tab's type
+stash copy --all: Copy every item in the open quad tab, no search needed (run reset_stash on an empty quad tab first)
+reset_stash: Sample the empty-cell colors of an open, empty quad tab
+identify: Identify every unidentified inventory item with the Scroll of Wisdom at the wisdom point
 pull <delay>: Change delay for pulling out of quad tab
 div <delay>: Change delay for div macro
+div-turnin: Trade every full stack in the open Divination Card tab until the inventory is full (trade window open; calibrate-div first, points trade, div-reward)
+chaos [sets] [--mode chaos|regal] [--vend] [stash source]: Click complete vendor-recipe sets from the chaos recipe tabs (tab-<name> points switch tabs); --vend sells each set and reopens the stash (point stash)
+vend: Sell the inventory to a vendor (points vendor, vendor-sell, vendor-accept; vendor-offer box must change before accepting)
+value <tab> | --file <json|txt> [--prices <file|dir>] [--top <n>]: Price a stash tab (or saved API response / stash copy dump) with poe.ninja exports (default: config prices/ folder)
+recipe <gcp|bauble|chromatic|jeweller> [sets] [--tab <name>]: List 40%-quality gem/flask sets, RGB-linked or 6-socket items in a quad tab and click out [sets] (0 = list only)
+snapshot [tab ...]: Save the tabs' contents as a compressed, timestamped snapshot (kept two weeks); without tabs, list snapshots
+diff [from [to]] [--prices <file|dir>]: Items added/removed between two snapshots (default: the last two), with value per hour when priced
+calib <export|import> <file>: Share regions, grids, points and inventory and quad tab colors (no session ids); import rescales to the current window size
+profile <list|use|save|copy|delete> [name] [to]: Named calibration sets (regions, grids, points, pointer scale); macros switch to the profile matching the game window size
+tabs [name ...]: List every stash tab (index, type, colour, item count) and save the recipe tabs' ids; names given become the recipe tabs
+tally [--mode chaos|regal] [stash source]: Count recipe items and complete sets in the chaos recipe tabs
+  stash source: --stash-file <json> reads a saved snapshot; --max-age <secs> reuses a cached response that young
+filter <file> <sets> [--mode chaos|regal] [--watch <secs>] [stash source]: Show recipe rares for slots under <sets> in a loot filter, hide the rest
 chrome <file> <times>: Open an auto-roll file, with name <file>, and roll item <times>
 mchrome <file>: Create example chrome file with name <file>. To be used with chrome later.
 
@@ -1207,7 +2470,10 @@ impl App {
                                 continue;
                             }
                         };
-                        if let Err(e) = save_config(&path, &*s) {
+                        if let Err(e) = s
+                            .to_disk()
+                            .and_then(|d| save_config(&path, &d).map_err(Into::into))
+                        {
                             println!("could not save config: {e}");
                         }
                     }
@@ -1285,6 +2551,174 @@ fn append_roll_log(path: &std::path::Path, source: &str, item_text: &str) -> io:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::colour::Matcher;
+
+    #[test]
+    fn occupied_inv_cells_skips_locked_cells() {
+        // 120x50 frame at the origin: a 10x10 px slot per cell, every pixel
+        // nonzero so nothing matches the all-zero "empty" samples.
+        let frame = ScreenshotData {
+            height: 50,
+            width: 120,
+            pixels: vec![0xFF; 120 * 50 * 4],
+            origin: (0, 0),
+        };
+        let region = ScreenRegion {
+            x: 0,
+            y: 0,
+            width: 120,
+            height: 50,
+        };
+        let mut samples = crate::colour::ProbeSamples {
+            colours: vec![[0u32; 3]; 60],
+            spread: Vec::new(),
+            matcher: Matcher::default(),
+        };
+        let mut locked = vec![false; 60];
+        locked[2 * 5 + 1] = true; // (col 2, row 1)
+
+        let all = App::occupied_inv_cells(&frame, region, &samples, &[]).unwrap();
+        assert_eq!(all.len(), 60);
+        let cells = App::occupied_inv_cells(&frame, region, &samples, &locked).unwrap();
+        assert_eq!(cells.len(), 59);
+        assert!(
+            !cells.contains(&(25, 15)),
+            "locked cell center must be skipped"
+        );
+
+        // A slightly darker calibration still reads every cell as empty.
+        samples.colours = vec![[0xF4F4F4FF; 3]; 60];
+        assert!(
+            App::occupied_inv_cells(&frame, region, &samples, &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn occupied_grid_cells_finds_items_without_a_search() {
+        // 240x240 dark frame, 10 px quad cells; a 2x2 item painted over
+        // cells (3, 4)..=(4, 5) with a slightly noisy background elsewhere.
+        let mut frame = ScreenshotData {
+            height: 240,
+            width: 240,
+            pixels: [0x20, 0x1C, 0x18, 0xFF].repeat(240 * 240),
+            origin: (0, 0),
+        };
+        for y in 40..60 {
+            for x in 30..50 {
+                let i = (y * 240 + x) * 4;
+                frame.pixels[i..i + 3].copy_from_slice(&[0xC0, 0x80, 0x40]);
+            }
+        }
+        let grid = crate::stash_grid::StashGrid::uniform((0.0, 0.0), 10.0, 0);
+        let samples = crate::colour::ProbeSamples {
+            colours: vec![[0x1C1C1CFF; 3]; QUAD_COLS * QUAD_ROWS],
+            spread: Vec::new(),
+            matcher: Matcher::default(),
+        };
+        assert_eq!(
+            App::occupied_grid_cells(&frame, &grid, &samples),
+            [(3, 4), (3, 5), (4, 4), (4, 5)]
+        );
+    }
+
+    #[test]
+    fn load_map_falls_back_to_the_first_occupied_cell() {
+        // 120x70 map grid of 10 px cells; maps at (5, 2) and (3, 4), nothing
+        // highlighted.
+        let mut frame = ScreenshotData {
+            height: 70,
+            width: 120,
+            pixels: [0x20, 0x1C, 0x18, 0xFF].repeat(120 * 70),
+            origin: (0, 0),
+        };
+        for (col, row) in [(5, 2), (3, 4)] {
+            for y in row * 10..row * 10 + 10 {
+                for x in col * 10..col * 10 + 10 {
+                    let i = (y * 120 + x) * 4;
+                    frame.pixels[i..i + 3].copy_from_slice(&[0x90, 0x90, 0x90]);
+                }
+            }
+        }
+        let grid =
+            crate::stash_grid::MapGrid::uniform((0.0, 0.0), 10.0, crate::layout::DEFAULT_HIGHLIGHT);
+        let mut s = crate::default_settings();
+        assert!(App::first_map_cell(&s, &grid, &frame).is_err());
+        s.map_samples = Some(vec![[0x1C1C1CFF; 3]; MAP_COLS * MAP_ROWS]);
+        assert_eq!(App::first_map_cell(&s, &grid, &frame).unwrap(), (5, 2));
+    }
+
+    #[test]
+    fn div_slots_split_the_tab_box_row_by_row() {
+        let tab = ScreenRegion {
+            x: 100,
+            y: 50,
+            width: 300,
+            height: 200,
+        };
+        let slots = App::div_slot_centers(tab, [3, 2]);
+        assert_eq!(slots.len(), 6);
+        assert_eq!(slots[0], (150, 100));
+        assert_eq!(slots[1], (250, 100));
+        assert_eq!(slots[5], (350, 200));
+    }
+
+    #[test]
+    fn positionals_skip_flags_and_their_values() {
+        let args: Vec<String> = ["chaos", "--mode", "regal", "3", "--vend", "--max-age", "60"]
+            .map(String::from)
+            .into();
+        let value_flags = ["--mode", "--stash-file", "--max-age"];
+        assert_eq!(
+            positionals(&args, &value_flags, &["--vend"]).unwrap(),
+            ["3"]
+        );
+        assert!(
+            positionals(&args[..3], &value_flags, &["--vend"])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn positionals_reject_unknown_flags() {
+        let args: Vec<String> = ["chaos", "3", "--vned"].map(String::from).into();
+        let err = positionals(&args, &["--mode"], &["--vend"]).unwrap_err();
+        assert!(err.to_string().contains("'--vned'"), "{err}");
+        assert!(err.to_string().contains("--mode, --vend"), "{err}");
+    }
+
+    #[test]
+    fn region_changed_only_looks_inside_the_offer_box() {
+        // 100x100 frame captured at screen (50, 50); the offer box covers
+        // frame pixels 10..40 on both axes.
+        let frame = |pixels| ScreenshotData {
+            height: 100,
+            width: 100,
+            pixels,
+            origin: (50, 50),
+        };
+        let before = frame(vec![0; 100 * 100 * 4]);
+        let mut outside = vec![0; 100 * 100 * 4];
+        let mut inside = outside.clone();
+        for y in 60..70 {
+            for x in 60..70 {
+                outside[(y * 100 + x) * 4..][..4].fill(0xFF);
+                inside[((y - 40) * 100 + x - 40) * 4..][..4].fill(0xFF);
+            }
+        }
+        let offer = ScreenRegion {
+            x: 60,
+            y: 60,
+            width: 30,
+            height: 30,
+        };
+        assert!(!App::region_changed(&before, &frame(outside), offer, Matcher::default()).unwrap());
+        assert!(App::region_changed(&before, &frame(inside), offer, Matcher::default()).unwrap());
+        let off_frame = ScreenRegion { x: 0, ..offer };
+        assert!(!App::region_changed(&before, &before, off_frame, Matcher::default()).unwrap());
+    }
 
     #[test]
     fn append_roll_log_writes_jsonl_records() {
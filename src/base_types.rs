@@ -0,0 +1,116 @@
+//! Embedded base-type database (`assets/base_types.json`): item class,
+//! inventory footprint, drop level, implicit mods and tags for every
+//! equipment base the macros deal with.
+//!
+//! Entries are matched by exact base name, e.g. `Iron Ring`, `Vaal Regalia`.
+//! Magic item names wrap the base in affixes, so [`find_in`] looks for the
+//! longest base name inside a name line instead.
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BaseType {
+    pub name: String,
+    /// The tooltip's `Item Class:` wording, e.g. `Body Armours`, `Wands`.
+    pub class: String,
+    /// Inventory footprint in cells.
+    pub w: u8,
+    pub h: u8,
+    #[allow(dead_code)]
+    pub drop_level: u8,
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub implicits: Vec<String>,
+    /// `weapon`, `one_hand_weapon`, `str_armour`, `ring`, …
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl BaseType {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+static BASES: Lazy<Vec<BaseType>> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../assets/base_types.json"))
+        .expect("assets/base_types.json is valid")
+});
+
+static BY_NAME: Lazy<HashMap<&'static str, &'static BaseType>> =
+    Lazy::new(|| BASES.iter().map(|b| (b.name.as_str(), b)).collect());
+
+/// Every base in the database.
+pub fn all() -> &'static [BaseType] {
+    &BASES
+}
+
+/// The base named exactly `name`.
+pub fn lookup(name: &str) -> Option<&'static BaseType> {
+    BY_NAME.get(name).copied()
+}
+
+/// The longest base name occurring in `line` as whole words, with its byte
+/// offset. `Phantasm's Silken Hood of the Thunderhead` finds `Silken Hood`
+/// at 11; a base inside a longer word (`Chain Boots` in `Chain Bootsx`)
+/// does not count.
+pub fn find_in(line: &str) -> Option<(&'static BaseType, usize)> {
+    let starts_word = |i: usize| line[..i].chars().next_back().is_none_or(|c| c == ' ');
+    let ends_word = |i: usize| line[i..].chars().next().is_none_or(|c| c == ' ');
+    BASES
+        .iter()
+        .filter_map(|b| {
+            line.match_indices(b.name.as_str())
+                .map(|(i, _)| i)
+                .find(|&i| starts_word(i) && ends_word(i + b.name.len()))
+                .map(|i| (b, i))
+        })
+        .max_by_key(|(b, _)| b.name.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_names_are_unique_and_sized() {
+        assert_eq!(BY_NAME.len(), BASES.len());
+        for b in BASES.iter() {
+            assert!(
+                (1..=2).contains(&b.w) && (1..=4).contains(&b.h),
+                "{}",
+                b.name
+            );
+            assert!(!b.class.is_empty() && !b.tags.is_empty(), "{}", b.name);
+        }
+    }
+
+    #[test]
+    fn lookup_covers_every_recipe_slot() {
+        for (name, class, w, h) in [
+            ("Vaal Regalia", "Body Armours", 2, 3),
+            ("Two-Toned Boots", "Boots", 2, 2),
+            ("Iron Ring", "Rings", 1, 1),
+            ("Leather Belt", "Belts", 2, 1),
+            ("Onyx Amulet", "Amulets", 1, 1),
+            ("Rusted Sword", "One Hand Swords", 1, 3),
+            ("Gnarled Branch", "Staves", 2, 4),
+        ] {
+            let b = lookup(name).unwrap();
+            assert_eq!((b.class.as_str(), b.w, b.h), (class, w, h), "{name}");
+        }
+        assert!(lookup("Iron Ring").unwrap().implicits[0].contains("Physical Damage"));
+        assert!(lookup("Mirror of Kalandra").is_none());
+    }
+
+    #[test]
+    fn find_in_picks_the_longest_whole_word_base() {
+        let (b, at) = find_in("Phantasm's Silken Hood of the Thunderhead").unwrap();
+        assert_eq!((b.name.as_str(), at), ("Silken Hood", 11));
+        let (b, _) = find_in("Hale Ringmail Gloves of the Fox").unwrap();
+        assert_eq!(b.name, "Ringmail Gloves");
+        assert!(find_in("Scroll of Wisdom").is_none());
+        assert!(find_in("Chain Bootsx").is_none());
+    }
+}
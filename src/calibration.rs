@@ -0,0 +1,369 @@
+//! `calib export|import`: calibration bundles to share between machines.
+//!
+//! A bundle is the geometry and colour part of a profile (regions, grids,
+//! points, inventory, quad and map tab samples) relative to the game window,
+//! plus the window size it was captured at. It is built field by field from
+//! [`Profile`], so recipe settings (session id, OAuth token) can never end up
+//! in it, and machine-specific values (pointer scale, locked cells) are left
+//! out.
+//!
+//! Import maps the bundle onto the current window. The same aspect ratio
+//! scales everything by the height ratio. Another aspect ratio still maps
+//! what PoE anchors to a window edge — the stash and map panels and their
+//! grids hug the left edge, the inventory the right — and reports named
+//! points outside those panels as unmapped. Colour samples only carry over
+//! at the bundle's own window size: cells blend differently once scaled, so
+//! a rescaled import resets them for the user to re-sample.
+use crate::profiles::Profile;
+use crate::stash_grid::CellGrid;
+use crate::{NamedPoint, ScreenRegion, Settings};
+use serde::{Deserialize, Serialize};
+
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bundle {
+    pub version: u32,
+    /// Game window size the bundle was captured at.
+    pub width: u32,
+    pub height: u32,
+    pub calibration: Profile,
+}
+
+/// What an import did, for the summary line.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub mapped: Vec<String>,
+    pub unmapped: Vec<String>,
+    /// Commands that re-sample the colour samples a rescaled import reset.
+    pub resample: Vec<&'static str>,
+}
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Left,
+    Right,
+}
+
+/// Source window → target window coordinate mapping (window-relative).
+struct Mapping {
+    from_w: f64,
+    to_w: f64,
+    k: f64,
+    same_aspect: bool,
+}
+
+impl Mapping {
+    fn new(from: (u32, u32), to: (u32, u32)) -> Mapping {
+        let aspect = |(w, h): (u32, u32)| w as f64 / h as f64;
+        Mapping {
+            from_w: from.0 as f64,
+            to_w: to.0 as f64,
+            k: to.1 as f64 / from.1 as f64,
+            same_aspect: (aspect(from) - aspect(to)).abs() < 0.01,
+        }
+    }
+
+    fn x(&self, x: f64, edge: Edge) -> f64 {
+        match edge {
+            _ if self.same_aspect => x * self.k,
+            Edge::Left => x * self.k,
+            Edge::Right => self.to_w - (self.from_w - x) * self.k,
+        }
+    }
+
+    /// Map a region whose left and right edges follow `left` and `right`.
+    fn region(&self, r: ScreenRegion, left: Edge, right: Edge) -> ScreenRegion {
+        let x0 = self.x(r.x as f64, left).max(0.0);
+        let x1 = self.x((r.x + r.width) as f64, right).max(x0);
+        ScreenRegion {
+            x: x0.round() as u32,
+            y: (r.y as f64 * self.k).round() as u32,
+            width: (x1 - x0).round() as u32,
+            height: (r.height as f64 * self.k).round() as u32,
+        }
+    }
+
+    /// Grids live in the left-anchored stash/map panels.
+    fn grid<const C: usize, const R: usize>(&self, g: &CellGrid<C, R>) -> CellGrid<C, R> {
+        let scale = |v: u32| (v as f64 * self.k).round() as u32;
+        CellGrid {
+            cols: g.cols.map(scale),
+            rows: g.rows.map(scale),
+            cell_w: scale(g.cell_w),
+            cell_h: scale(g.cell_h),
+            highlight_color: g.highlight_color,
+            highlight_spread: g.highlight_spread,
+        }
+    }
+}
+
+fn contains(outer: Option<ScreenRegion>, p: ScreenRegion) -> bool {
+    let (cx, cy) = p.center();
+    outer.is_some_and(|o| cx >= o.x && cy >= o.y && cx < o.x + o.width && cy < o.y + o.height)
+}
+
+impl Settings {
+    /// The live calibration as a shareable bundle.
+    pub(crate) fn export_bundle(&self) -> anyhow::Result<Bundle> {
+        let mut relative = self.clone();
+        relative.window_anchor = self.window_anchor.or(self.game_window_region);
+        let Some(anchor) = relative.window_anchor else {
+            anyhow::bail!("Game window region not set — run: little_oil set-region window");
+        };
+        let p = relative.current_profile()?;
+        Ok(Bundle {
+            version: BUNDLE_VERSION,
+            width: anchor.width,
+            height: anchor.height,
+            calibration: Profile {
+                game_window_region: p.game_window_region,
+                window_anchor: None,
+                inv_region: p.inv_region,
+                inv_samples: p.inv_samples,
+                inv_sample_spread: p.inv_sample_spread,
+                stash_samples: p.stash_samples,
+                stash_sample_spread: p.stash_sample_spread,
+                map_samples: p.map_samples,
+                map_sample_spread: p.map_sample_spread,
+                inv_locked: None,
+                stash_region: p.stash_region,
+                stash_grid: p.stash_grid,
+                normal_grid: p.normal_grid,
+                map_region: p.map_region,
+                map_grid: p.map_grid,
+                points: p.points,
+                pointer_scale: None,
+            },
+        })
+    }
+
+    /// Map `bundle` onto the game window at `window` and merge it into the
+    /// live calibration. Entries missing from the bundle, and points it could
+    /// not map, keep their live values.
+    pub(crate) fn import_bundle(
+        &mut self,
+        bundle: &Bundle,
+        window: ScreenRegion,
+    ) -> anyhow::Result<ImportReport> {
+        if bundle.version != BUNDLE_VERSION {
+            anyhow::bail!(
+                "Calibration bundle version {} is not supported (expected {BUNDLE_VERSION})",
+                bundle.version
+            );
+        }
+        if self
+            .window_anchor
+            .is_some_and(|a| (a.width, a.height) == (window.width, window.height))
+        {
+            self.reanchor(window)?;
+        }
+        self.window_anchor = Some(window);
+
+        let m = Mapping::new((bundle.width, bundle.height), (window.width, window.height));
+        let c = &bundle.calibration;
+        let mut report = ImportReport::default();
+        let place = |r: ScreenRegion| ScreenRegion {
+            x: window.x + r.x,
+            y: window.y + r.y,
+            ..r
+        };
+
+        let regions = [
+            ("game window", c.game_window_region, Edge::Left, Edge::Right),
+            ("inventory", c.inv_region, Edge::Right, Edge::Right),
+            ("stash", c.stash_region, Edge::Left, Edge::Left),
+            ("map", c.map_region, Edge::Left, Edge::Left),
+        ];
+        for (name, region, left, right) in regions {
+            let Some(r) = region else { continue };
+            let mapped = Some(place(m.region(r, left, right)));
+            match name {
+                "game window" => self.game_window_region = mapped,
+                "inventory" => self.inv_region = mapped,
+                "stash" => self.stash_region = mapped,
+                _ => self.map_region = mapped,
+            }
+            report.mapped.push(format!("{name} region"));
+        }
+        if let Some(g) = &c.stash_grid {
+            self.stash_grid = Some(m.grid(g));
+            report.mapped.push("quad grid".into());
+        }
+        if let Some(g) = &c.normal_grid {
+            self.normal_grid = Some(m.grid(g));
+            report.mapped.push("normal grid".into());
+        }
+        if let Some(g) = &c.map_grid {
+            self.map_grid = Some(m.grid(g));
+            report.mapped.push("map grid".into());
+        }
+        let rescaled = (bundle.width, bundle.height) != (window.width, window.height);
+        let samples = [
+            (
+                "inventory samples",
+                "reset_inv",
+                &c.inv_samples,
+                &c.inv_sample_spread,
+                &mut self.inv_samples,
+                &mut self.inv_sample_spread,
+            ),
+            (
+                "quad tab samples",
+                "reset_stash",
+                &c.stash_samples,
+                &c.stash_sample_spread,
+                &mut self.stash_samples,
+                &mut self.stash_sample_spread,
+            ),
+            (
+                "map tab samples",
+                "reset_map",
+                &c.map_samples,
+                &c.map_sample_spread,
+                &mut self.map_samples,
+                &mut self.map_sample_spread,
+            ),
+        ];
+        for (name, command, bundled, spread, live, live_spread) in samples {
+            if bundled.is_none() {
+                continue;
+            }
+            if rescaled {
+                (*live, *live_spread) = (None, None);
+                report.resample.push(command);
+            } else {
+                (*live, *live_spread) = (bundled.clone(), spread.clone());
+                report.mapped.push(name.into());
+            }
+        }
+
+        let mut points = self.points.take().unwrap_or_default();
+        for p in c.points.iter().flatten() {
+            let edge = if m.same_aspect
+                || contains(c.stash_region, p.region)
+                || contains(c.map_region, p.region)
+            {
+                Some((Edge::Left, Edge::Left))
+            } else if contains(c.inv_region, p.region) {
+                Some((Edge::Right, Edge::Right))
+            } else {
+                None
+            };
+            let Some((left, right)) = edge else {
+                report.unmapped.push(p.name.clone());
+                continue;
+            };
+            points.retain(|live| live.name != p.name);
+            points.push(NamedPoint {
+                name: p.name.clone(),
+                region: place(m.region(p.region, left, right)),
+            });
+            report.mapped.push(format!("point {}", p.name));
+        }
+        self.points = Some(points);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> ScreenRegion {
+        ScreenRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn point(name: &str, region: ScreenRegion) -> NamedPoint {
+        NamedPoint {
+            name: name.into(),
+            region,
+        }
+    }
+
+    fn exported() -> Bundle {
+        let mut s = crate::default_settings();
+        s.window_anchor = Some(region(100, 0, 1920, 1080));
+        s.game_window_region = Some(region(100, 0, 1920, 1080));
+        s.stash_region = Some(region(116, 160, 632, 632));
+        s.inv_region = Some(region(1372, 588, 632, 263));
+        s.inv_samples = Some(vec![[0x101010FF; 3]; 60]);
+        s.inv_sample_spread = Some(vec![[2; 3]; 60]);
+        s.points = Some(vec![
+            point("chaos", region(300, 180, 40, 40)),
+            point("inv-corner", region(1380, 600, 40, 40)),
+            point("filter", region(1000, 20, 40, 40)),
+        ]);
+        s.chaos_recipe_settings = Some(
+            serde_json::from_str(
+                r#"{"session_id": "SECRET", "league": "Standard", "tab_name": "chaos", "tab_index": 0}"#,
+            )
+            .unwrap(),
+        );
+        s.export_bundle().unwrap()
+    }
+
+    fn find(s: &Settings, name: &str) -> ScreenRegion {
+        s.points
+            .iter()
+            .flatten()
+            .find(|p| p.name == name)
+            .unwrap()
+            .region
+    }
+
+    #[test]
+    fn export_is_window_relative_and_secret_free() {
+        let b = exported();
+        assert_eq!((b.width, b.height), (1920, 1080));
+        assert_eq!(b.calibration.stash_region.unwrap().x, 16);
+        let json = serde_json::to_string(&b).unwrap();
+        assert!(!json.contains("SECRET") && !json.contains("session_id"));
+        assert!(json.contains("\"pointer_scale\":null"));
+    }
+
+    #[test]
+    fn import_scales_same_aspect_and_reports_free_points_otherwise() {
+        let b = exported();
+
+        let mut s = crate::default_settings();
+        let report = s.import_bundle(&b, region(0, 0, 2560, 1440)).unwrap();
+        assert!(report.unmapped.is_empty());
+        assert_eq!(s.stash_region.unwrap().width, 843);
+        assert_eq!(find(&s, "filter").x, 1200);
+
+        // Ultrawide: the stash scales from the left edge, the inventory
+        // from the right, and the free-floating filter point is reported.
+        let mut s = crate::default_settings();
+        let report = s.import_bundle(&b, region(0, 0, 3440, 1440)).unwrap();
+        assert_eq!(report.unmapped, ["filter"]);
+        assert_eq!(find(&s, "chaos").x, 267);
+        assert_eq!(s.inv_region.unwrap().x + 843, 3440 - 21);
+        assert_eq!(find(&s, "inv-corner").x, 3440 - 853);
+        assert_eq!(s.game_window_region.unwrap().width, 3440);
+    }
+
+    #[test]
+    fn samples_carry_over_only_at_the_bundle_size() {
+        let b = exported();
+
+        let mut s = crate::default_settings();
+        let report = s.import_bundle(&b, region(0, 0, 1920, 1080)).unwrap();
+        assert!(report.resample.is_empty());
+        assert_eq!(s.inv_samples.as_ref().map(Vec::len), Some(60));
+        assert!(s.inv_sample_spread.is_some());
+
+        // Rescaled: the bundled samples are dropped and so are the live ones
+        // they would have replaced; samples the bundle lacks stay.
+        s.map_samples = Some(vec![[0x202020FF; 3]; 84]);
+        let report = s.import_bundle(&b, region(0, 0, 2560, 1440)).unwrap();
+        assert_eq!(report.resample, ["reset_inv"]);
+        assert!(s.inv_samples.is_none() && s.inv_sample_spread.is_none());
+        assert!(s.map_samples.is_some());
+    }
+}
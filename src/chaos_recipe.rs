@@ -1,37 +1,118 @@
 //! Chaos-recipe bot: query the PoE stash API and click the matching items into
 //! the quad tab.
-use anyhow::bail;
+//!
+//! Stash contents come from the OAuth API (`stash_api`) when `oauth_token` is
+//! set, otherwise from the legacy `character-window` endpoint with the
+//! `POESESSID` cookie.
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::stash_api::{self, OAuthStash};
+use crate::stash_cache;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChaosRecipe {
+    #[serde(default)]
     session_id: String,
+    #[serde(default)]
     account_name: String,
     league: String,
     tab_name: String,
+    /// Where `tab_name` was last found. Only a hint for the legacy
+    /// endpoint's first request; tabs are resolved through `tab_ids`.
     tab_index: Option<usize>,
+    /// More tabs fetched together with `tab_name`; sets are planned across
+    /// all of them and each needs a `tab-<name>` point to switch to it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tab_names: Vec<String>,
+    /// Stash tab id per configured tab name, written by `tabs` and by every
+    /// fetch. Ids survive reordering (and renaming) tabs in game; a name
+    /// whose id is gone is looked up by name again and its id replaced.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tab_ids: BTreeMap<String, String>,
+    /// OAuth bearer token with the `account:stashes` scope. When set, the
+    /// `api.pathofexile.com` stash endpoints are used instead of POESESSID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oauth_token: Option<String>,
+    /// API root override; defaults to `stash_api::API_BASE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
 }
 
-#[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct Color {
     r: usize,
     g: usize,
     b: usize,
 }
 
-#[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+impl Color {
+    /// `"7c5436"` (OAuth tab metadata) to components.
+    fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.trim_start_matches('#');
+        let n = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        Some(Color {
+            r: (n >> 16 & 0xff) as usize,
+            g: (n >> 8 & 0xff) as usize,
+            b: (n & 0xff) as usize,
+        })
+    }
+
+    /// A two-cell terminal colour block followed by the hex code.
+    fn swatch(&self) -> String {
+        format!(
+            "\x1b[48;2;{r};{g};{b}m  \x1b[0m {r:02x}{g:02x}{b:02x}",
+            r = self.r,
+            g = self.g,
+            b = self.b
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct StashTab {
     n: String,
     i: usize,
     id: String,
     colour: Color,
+    /// `QuadStash`, `CurrencyStash`, `PremiumStash`, …
+    #[serde(rename = "type", default)]
+    kind: String,
+}
+
+impl StashTab {
+    fn from_info(pos: usize, t: &stash_api::StashTabInfo) -> StashTab {
+        StashTab {
+            n: t.name.clone(),
+            i: t.index.unwrap_or(pos),
+            id: t.id.clone(),
+            colour: t
+                .metadata
+                .colour
+                .as_deref()
+                .and_then(Color::from_hex)
+                .unwrap_or(Color { r: 0, g: 0, b: 0 }),
+            kind: t.kind.clone(),
+        }
+    }
+
+    /// Short type name for listings: `quad`, `currency`, `map`, `fragment`, …
+    fn kind_label(&self) -> String {
+        match self.kind.as_str() {
+            "" => "?".into(),
+            "DivinationCardStash" => "divination".into(),
+            k => k.strip_suffix("Stash").unwrap_or(k).to_lowercase(),
+        }
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct StashAPIResult {
     num_tabs: usize,
@@ -39,10 +120,17 @@ struct StashAPIResult {
     quad_layout: bool,
     items: Vec<Item>,
     tabs: Vec<StashTab>,
+    /// Names of the fetched tabs; `Item::tab` indexes this.
+    #[serde(default)]
+    fetched: Vec<String>,
+    /// The fetched tabs as resolved from the tab list, aligned with
+    /// `fetched`. Their type picks the grid each tab is clicked through.
+    #[serde(default)]
+    fetched_tabs: Vec<StashTab>,
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct Item {
     x: usize,
@@ -55,10 +143,76 @@ struct Item {
     w: usize,
     h: usize,
     properties: Option<Vec<serde_json::Value>>,
+    /// 0 normal, 1 magic, 2 rare, 3 unique, …
+    #[serde(default)]
+    frame_type: u8,
+    /// Which fetched tab the item sits in.
+    #[serde(default)]
+    tab: usize,
     #[serde(default)]
     used: bool,
 }
 
+const FRAME_RARE: u8 = 2;
+
+/// Where stash contents come from. `--stash-file <json>` reads a saved
+/// snapshot and never touches the network; `--max-age <secs>` reuses the
+/// newest cached response when it is at most that old. Otherwise the API
+/// is asked, and every successful response is cached.
+#[derive(Debug, Clone, Default)]
+pub struct StashSource {
+    pub file: Option<PathBuf>,
+    pub max_age: Option<u64>,
+}
+
+impl StashSource {
+    pub fn from_args(args: &[String]) -> anyhow::Result<StashSource> {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .map(|i| {
+                    args.get(i + 1)
+                        .ok_or_else(|| anyhow::anyhow!("{flag} needs a value"))
+                })
+                .transpose()
+        };
+        Ok(StashSource {
+            file: value("--stash-file")?.map(PathBuf::from),
+            max_age: value("--max-age")?.map(|v| v.parse()).transpose()?,
+        })
+    }
+}
+
+/// Which vendor recipe a set is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeMode {
+    /// At least one item at ilvl 60–74, the rest 60+.
+    Chaos,
+    /// Every item at ilvl 75+.
+    Regal,
+}
+
+impl RecipeMode {
+    /// `--mode chaos|regal` anywhere in `args`; chaos when absent.
+    pub fn from_args(args: &[String]) -> anyhow::Result<RecipeMode> {
+        let Some(pos) = args.iter().position(|a| a == "--mode") else {
+            return Ok(RecipeMode::Chaos);
+        };
+        match args.get(pos + 1).map(|m| m.as_str()) {
+            Some("chaos") => Ok(RecipeMode::Chaos),
+            Some("regal") => Ok(RecipeMode::Regal),
+            _ => anyhow::bail!("--mode takes chaos or regal"),
+        }
+    }
+
+    pub(crate) fn min_ilvl(self) -> usize {
+        match self {
+            RecipeMode::Chaos => 60,
+            RecipeMode::Regal => 75,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ItemType {
     Weapon,
@@ -74,6 +228,30 @@ enum ItemType {
     Unknown,
 }
 
+/// Click order inside a set. Also the planner's slot groups.
+pub(crate) const SLOT_NAMES: [&str; 8] = [
+    "Body", "Helmet", "Boots", "Gloves", "Belt", "Amulet", "Ring", "Weapon",
+];
+const RING: usize = 6;
+const WEAPON: usize = 7;
+
+impl ItemType {
+    /// Index into `SLOT_NAMES`.
+    fn slot(&self) -> Option<usize> {
+        match self {
+            ItemType::Body => Some(0),
+            ItemType::Helmet => Some(1),
+            ItemType::Boots => Some(2),
+            ItemType::Gloves => Some(3),
+            ItemType::Belt => Some(4),
+            ItemType::Amulet => Some(5),
+            ItemType::Ring => Some(RING),
+            ItemType::Weapon => Some(WEAPON),
+            ItemType::Unknown => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct ItemCount {
     weapon: usize,
@@ -87,77 +265,88 @@ struct ItemCount {
     other: usize,
 }
 
-fn check_help(items: &[&str], base: &str) -> bool {
-    for item in items {
-        if item == &base {
-            return true;
-        }
-    }
-    false
+/// How a weapon fills a set's weapon slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WeaponFit {
+    /// Fills it alone.
+    TwoHand,
+    /// 1x3 or smaller; two of them fill it.
+    OneHand,
 }
 
-impl Item {
-    fn get_category(&self) -> ItemType {
-        use crate::dicts::*;
-
-        if self.is_weapon() {
-            return ItemType::Weapon;
-        }
-
-        if check_help(BOOTS, &self.base_type) {
-            return ItemType::Boots;
-        }
-
-        if check_help(HELMETS, &self.base_type) {
-            return ItemType::Helmet;
-        }
-
-        if check_help(GLOVES, &self.base_type) {
-            return ItemType::Gloves;
-        }
-
-        if check_help(BODY, &self.base_type) {
-            return ItemType::Body;
-        }
+/// How a `w`x`h` item of `base` fills a set's weapon slot, from the
+/// base-type tags. None for non-weapons and for one-handers bigger than 1x3
+/// (claws, axes, maces, sceptres, thrusting swords): a set takes two
+/// one-handers only when both fit a 1x3 column.
+fn weapon_fit(base: &crate::base_types::BaseType, w: usize, h: usize) -> Option<WeaponFit> {
+    if base.has_tag("two_hand_weapon") {
+        Some(WeaponFit::TwoHand)
+    } else if base.has_tag("one_hand_weapon") && w == 1 && h <= 3 {
+        Some(WeaponFit::OneHand)
+    } else {
+        None
+    }
+}
 
-        if self.base_type.contains("Ring") {
-            return ItemType::Ring;
-        }
+/// Item classes whose bases the planner puts in a set's weapon slot,
+/// sorted. The loot filter's weapon rule shows exactly these.
+pub(crate) fn recipe_weapon_classes() -> Vec<&'static str> {
+    let mut classes: Vec<&str> = crate::base_types::all()
+        .iter()
+        .filter(|b| weapon_fit(b, b.w.into(), b.h.into()).is_some())
+        .map(|b| b.class.as_str())
+        .collect();
+    classes.sort();
+    classes.dedup();
+    classes
+}
 
-        if self.base_type.contains("Belt") || self.base_type == "Rustic Sash" {
-            return ItemType::Belt;
-        }
+impl Item {
+    /// Whether the item can go into a `mode` set at all (the chaos set's
+    /// one-low-item requirement is checked per set).
+    fn eligible(&self, mode: RecipeMode) -> bool {
+        self.frame_type == FRAME_RARE && self.ilvl >= mode.min_ilvl()
+    }
 
-        if self.base_type.contains("Amulet") {
-            return ItemType::Amulet;
-        }
+    fn is_low(&self) -> bool {
+        (60..75).contains(&self.ilvl)
+    }
 
-        ItemType::Unknown
+    /// How the item fills a set's weapon slot (see [`weapon_fit`]).
+    fn weapon_fit(&self) -> Option<WeaponFit> {
+        weapon_fit(crate::base_types::lookup(&self.base_type)?, self.w, self.h)
     }
 
-    fn is_weapon(&self) -> bool {
-        let props = match &self.properties {
-            Some(s) => s,
-            None => return false,
+    /// Recipe slot from the base-type database's item class.
+    fn get_category(&self) -> ItemType {
+        let Some(base) = crate::base_types::lookup(&self.base_type) else {
+            return ItemType::Unknown;
         };
-
-        for prop in props {
-            let hasaps = prop
-                .get("name")
-                .and_then(|name| name.as_str())
-                .map(|name| name == "Attacks per Second");
-
-            if hasaps == Some(true) {
-                return true;
-            }
+        match base.class.as_str() {
+            "Body Armours" => ItemType::Body,
+            "Helmets" => ItemType::Helmet,
+            "Gloves" => ItemType::Gloves,
+            "Boots" => ItemType::Boots,
+            "Belts" => ItemType::Belt,
+            "Amulets" => ItemType::Amulet,
+            "Rings" => ItemType::Ring,
+            _ if base.has_tag("weapon") => ItemType::Weapon,
+            _ => ItemType::Unknown,
         }
-
-        false
     }
 }
 
 impl ChaosRecipe {
-    fn get_url(&self) -> String {
+    pub fn league(&self) -> &str {
+        &self.league
+    }
+
+    /// The configured `tab_name`.
+    pub fn primary_tab(&self) -> &str {
+        &self.tab_name
+    }
+
+    fn get_url(&self, index: usize) -> String {
         //let u = format!(
         //"https://www.pathofexile.com/character-window/get-stash-items?accountName={}&realm=pc&league={}&tabs=1&tabIndex={}",
         //self.account_name,
@@ -173,141 +362,517 @@ impl ChaosRecipe {
 
         format!(
             "https://www.pathofexile.com/character-window/get-stash-items?accountName={}&realm=pc&league={}&tabs=1&tabIndex={}",
+            self.account_name, self.league, index,
+        )
+    }
+
+    /// `tab_name` first, then `tab_names` without repeats.
+    fn tab_list(&self) -> Vec<&str> {
+        let mut names = vec![self.tab_name.as_str()];
+        for n in &self.tab_names {
+            if !names.contains(&n.as_str()) {
+                names.push(n);
+            }
+        }
+        names
+    }
+
+    /// Fetch every recipe tab from `source`. A fresh API fetch records where
+    /// the tabs were found (see [`Self::remember_tabs`]) and is saved to the
+    /// stash cache.
+    fn get_json(&self, app: &crate::App, source: &StashSource) -> anyhow::Result<StashAPIResult> {
+        let tabs = self.tab_list();
+        if let Some(path) = &source.file {
+            let mut apir: StashAPIResult = stash_cache::load_file(path)?;
+            if apir.fetched.is_empty() {
+                apir.fetched = tabs.iter().map(|t| t.to_string()).collect();
+            }
+            self.resolve_fetched(&mut apir);
+            println!("Using stash snapshot {}", path.display());
+            return Ok(apir);
+        }
+
+        let root = crate::stash_cache_dir()?;
+        if let Some(max_age) = source.max_age
+            && let Some(entry) = stash_cache::latest::<StashAPIResult>(&root, &self.league, &tabs)
+            && entry.age_secs() <= max_age
+        {
+            println!("Using cached stash from {}s ago", entry.age_secs());
+            let mut apir = entry.data;
+            self.resolve_fetched(&mut apir);
+            return Ok(apir);
+        }
+
+        let (apir, found) = match &self.oauth_token {
+            Some(token) => self.fetch_oauth(token)?,
+            None => self.fetch_legacy()?,
+        };
+        self.remember_tabs(app, &tabs, &found)?;
+        if let Err(e) = stash_cache::save(&root, &self.league, &tabs, &apir) {
+            tracing::warn!("could not cache stash response: {e:#}");
+        }
+        Ok(apir)
+    }
+
+    /// The tab the configured `name` refers to: the saved id while that tab
+    /// still exists, otherwise whichever tab is called `name` now.
+    fn resolve<'t>(&self, tabs: &'t [StashTab], name: &str) -> Option<&'t StashTab> {
+        self.tab_ids
+            .get(name)
+            .and_then(|id| tabs.iter().find(|t| t.id == *id))
+            .or_else(|| tabs.iter().find(|t| t.n == name))
+    }
+
+    /// Fill `fetched_tabs` of a snapshot saved without them by resolving
+    /// each fetched name against the snapshot's own tab list. Stops at the
+    /// first name it cannot resolve, so the list stays aligned.
+    fn resolve_fetched(&self, apir: &mut StashAPIResult) {
+        if apir.fetched_tabs.is_empty() {
+            apir.fetched_tabs = apir
+                .fetched
+                .iter()
+                .map_while(|name| self.resolve(&apir.tabs, name).cloned())
+                .collect();
+        }
+    }
+
+    /// Save the ids of `found` (aligned with `names`) and the first one's
+    /// index into config.json when they changed, so reordered tabs are
+    /// still found next time.
+    fn remember_tabs(
+        &self,
+        app: &crate::App,
+        names: &[&str],
+        found: &[StashTab],
+    ) -> anyhow::Result<()> {
+        let mut ids = self.tab_ids.clone();
+        ids.extend(
+            names
+                .iter()
+                .zip(found)
+                .map(|(n, t)| (n.to_string(), t.id.clone())),
+        );
+        let index = found.first().map(|t| t.i).or(self.tab_index);
+        if ids == self.tab_ids && index == self.tab_index {
+            return Ok(());
+        }
+        let mut settings = app.settings.write();
+        if let Some(s) = settings.chaos_recipe_settings.as_mut() {
+            s.tab_ids = ids;
+            s.tab_index = index;
+        }
+        crate::save_config(&crate::config_path()?, &settings.to_disk()?)?;
+        println!("Saved stash tab ids for {} to config", names.join(", "));
+        Ok(())
+    }
+
+    fn missing_tab(&self, name: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "No stash tab named '{}' found — check chaos_recipe_settings (account '{}', league '{}') in config.json, or run: little_oil tabs",
+            name,
             self.account_name,
-            self.league,
-            self.tab_index.unwrap_or(0),
+            self.league
         )
     }
 
-    fn get_json(&self, app: &crate::App) -> anyhow::Result<StashAPIResult> {
-        let resp = ureq::get(&self.get_url())
-            .header("Accept", "application/json")
-            .header("Cookie", &format!("POESESSID={}", self.session_id))
-            .call()
-            .map_err(|e| anyhow::anyhow!("failed to fetch stash tab from pathofexile.com: {e}"))?;
-        let apir: StashAPIResult = resp
-            .into_body()
-            .read_json()
-            .map_err(|e| anyhow::anyhow!("failed to parse stash tab JSON: {e}"))?;
-
-        let mut index_matched = false;
-        for tab in &apir.tabs {
-            if Some(tab.i) == self.tab_index {
-                index_matched = true;
-                println!("Chaos recipe tab name is {}", tab.n);
-                println!("Config file name is {}", self.tab_name);
-            } else if tab.n == self.tab_name {
-                println!("closest ID is {}, {}", tab.n, tab.i);
-                let mut settings = app.settings.write();
-                if let Some(s) = settings.chaos_recipe_settings.as_mut() {
-                    s.tab_index = Some(tab.i);
+    /// GET one tab from the legacy endpoint, paced by
+    /// [`stash_api::get_paced`] like the OAuth client.
+    fn legacy_request<T: DeserializeOwned>(&self, index: usize) -> anyhow::Result<T> {
+        let url = self.get_url(index);
+        let request = || {
+            ureq::get(&url)
+                .header("Accept", "application/json")
+                .header("Cookie", &format!("POESESSID={}", self.session_id))
+        };
+        stash_api::get_paced("pathofexile.com", request, |status| {
+            anyhow::anyhow!("pathofexile.com returned HTTP {status} for stash tab {index}")
+        })
+    }
+
+    /// Legacy endpoint. Every response lists all tabs, so the first request
+    /// (at the `tab_index` hint) resolves the recipe tabs and each one not
+    /// already returned is fetched by its current index.
+    fn fetch_legacy(&self) -> anyhow::Result<(StashAPIResult, Vec<StashTab>)> {
+        let hint = self.tab_index.unwrap_or(0);
+        let mut apir: StashAPIResult = self.legacy_request(hint)?;
+        let first = std::mem::take(&mut apir.items);
+        let names = self.tab_list();
+        let mut found = Vec::new();
+        for (k, name) in names.iter().enumerate() {
+            let tab = self
+                .resolve(&apir.tabs, name)
+                .ok_or_else(|| self.missing_tab(name))?
+                .clone();
+            let items = if tab.i == hint {
+                first.clone()
+            } else {
+                let more: StashAPIResult = self.legacy_request(tab.i)?;
+                if k == 0 {
+                    apir.quad_layout = more.quad_layout;
                 }
-                crate::save_config(&crate::config_path()?, &*settings)?;
-                println!("writing config {:?}", settings);
+                more.items
+            };
+            apir.items
+                .extend(items.into_iter().map(|item| Item { tab: k, ..item }));
+            found.push(tab);
+        }
+        apir.fetched = names.iter().map(|n| n.to_string()).collect();
+        apir.fetched_tabs = found.clone();
+        Ok((apir, found))
+    }
+
+    /// OAuth endpoint: list tabs, resolve each recipe tab, then fetch them
+    /// by id.
+    fn fetch_oauth(&self, token: &str) -> anyhow::Result<(StashAPIResult, Vec<StashTab>)> {
+        let api = self.oauth(token);
+        let tabs: Vec<StashTab> = api
+            .list_tabs()?
+            .iter()
+            .enumerate()
+            .map(|(pos, t)| StashTab::from_info(pos, t))
+            .collect();
+
+        let names = self.tab_list();
+        let mut items = Vec::new();
+        let mut found = Vec::new();
+        for (k, name) in names.iter().enumerate() {
+            let tab = self
+                .resolve(&tabs, name)
+                .ok_or_else(|| self.missing_tab(name))?
+                .clone();
+            let contents = api.fetch_tab::<Item>(&tab.id)?;
+            items.extend(
+                contents
+                    .items
+                    .into_iter()
+                    .map(|item| Item { tab: k, ..item }),
+            );
+            found.push(tab);
+        }
+        Ok((
+            StashAPIResult {
+                num_tabs: tabs.len(),
+                quad_layout: found[0].kind == "QuadStash",
+                items,
+                tabs,
+                fetched: names.iter().map(|n| n.to_string()).collect(),
+                fetched_tabs: found.clone(),
+            },
+            found,
+        ))
+    }
 
-                let mut newc = self.clone();
-                newc.tab_index = Some(tab.i);
-                //TODO safety
-                return newc.get_json(app);
+    fn oauth<'a>(&'a self, token: &'a str) -> OAuthStash<'a> {
+        OAuthStash {
+            base: self.api_base.as_deref().unwrap_or(stash_api::API_BASE),
+            token,
+            league: &self.league,
+        }
+    }
+
+    /// Every stash tab with its item count. One request per tab; legacy
+    /// requests are paced by [`Self::legacy_request`].
+    fn catalogue(&self) -> anyhow::Result<Vec<(StashTab, usize)>> {
+        match &self.oauth_token {
+            Some(token) => {
+                let api = self.oauth(token);
+                let mut out = Vec::new();
+                for (pos, info) in api.list_tabs()?.iter().enumerate() {
+                    let count = api.fetch_tab::<serde_json::Value>(&info.id)?.items.len();
+                    out.push((StashTab::from_info(pos, info), count));
+                }
+                Ok(out)
+            }
+            None => {
+                let hint = self.tab_index.unwrap_or(0);
+                let first: StashAPIResult = self.legacy_request(hint)?;
+                let mut out = Vec::new();
+                for tab in &first.tabs {
+                    let count = if tab.i == hint {
+                        first.items.len()
+                    } else {
+                        self.legacy_request::<StashAPIResult>(tab.i)?.items.len()
+                    };
+                    out.push((tab.clone(), count));
+                }
+                Ok(out)
             }
         }
+    }
 
-        if !index_matched {
-            bail!(
-                "No stash tab named '{}' found — check chaos_recipe_settings (account '{}', league '{}') in config.json",
-                self.tab_name,
-                self.account_name,
-                self.league
-            );
+    /// Any one tab's items, resolved like the recipe tabs, deserialized into
+    /// the caller's item type.
+    fn named_tab_items<I: DeserializeOwned>(&self, name: &str) -> anyhow::Result<Vec<I>> {
+        if let Some(token) = &self.oauth_token {
+            let api = self.oauth(token);
+            let infos = api.list_tabs()?;
+            let tabs: Vec<StashTab> = infos
+                .iter()
+                .enumerate()
+                .map(|(pos, t)| StashTab::from_info(pos, t))
+                .collect();
+            let tab = self
+                .resolve(&tabs, name)
+                .ok_or_else(|| self.missing_tab(name))?;
+            return Ok(api.fetch_tab::<I>(&tab.id)?.items);
         }
+        #[derive(Deserialize)]
+        struct Response<I> {
+            tabs: Vec<StashTab>,
+            items: Vec<I>,
+        }
+        let hint = self.tab_index.unwrap_or(0);
+        let first: Response<serde_json::Value> = self.legacy_request(hint)?;
+        let tab = self
+            .resolve(&first.tabs, name)
+            .ok_or_else(|| self.missing_tab(name))?;
+        if tab.i == hint {
+            return first
+                .items
+                .into_iter()
+                .map(|v| serde_json::from_value(v).map_err(Into::into))
+                .collect();
+        }
+        Ok(self.legacy_request::<Response<I>>(tab.i)?.items)
+    }
+}
 
-        Ok(apir)
+/// Whether the stash tab called `name` is a quad tab, from the tab list.
+pub fn tab_is_quad(cr_config: &ChaosRecipe, name: &str) -> anyhow::Result<bool> {
+    let tabs: Vec<StashTab> = match &cr_config.oauth_token {
+        Some(token) => cr_config
+            .oauth(token)
+            .list_tabs()?
+            .iter()
+            .enumerate()
+            .map(|(pos, t)| StashTab::from_info(pos, t))
+            .collect(),
+        None => {
+            cr_config
+                .legacy_request::<StashAPIResult>(cr_config.tab_index.unwrap_or(0))?
+                .tabs
+        }
+    };
+    let tab = cr_config
+        .resolve(&tabs, name)
+        .ok_or_else(|| cr_config.missing_tab(name))?;
+    Ok(tab.kind == "QuadStash")
+}
+
+/// Items of the stash tab called `name` (see `pricing`).
+pub fn tab_items<I: DeserializeOwned>(
+    cr_config: &ChaosRecipe,
+    name: &str,
+) -> anyhow::Result<Vec<I>> {
+    cr_config.named_tab_items(name)
+}
+
+/// Unused eligible items of one slot group, split by the chaos ilvl band,
+/// each half unidentified first.
+#[derive(Default)]
+struct Pool {
+    low: Vec<usize>,
+    high: Vec<usize>,
+}
+
+impl Pool {
+    fn len(&self) -> usize {
+        self.low.len() + self.high.len()
+    }
+
+    /// `k` low items, and `n - k` more that prefer high ones so low items
+    /// are only spent where a set needs them.
+    fn take(&self, n: usize, k: usize) -> (Vec<usize>, Vec<usize>) {
+        let rest = self.high.iter().chain(&self.low[k..]).copied();
+        (self.low[..k].to_vec(), rest.take(n - k).collect())
     }
 }
 
-#[derive(Default, Debug)]
-struct ItemList<'a> {
-    weapon1: Option<&'a Item>,
-    weapon2: Option<&'a Item>,
-    ring1: Option<&'a Item>,
-    ring2: Option<&'a Item>,
+/// How to fill `n` weapon units from two-handers and one-hand pairs: the
+/// number of two-handers that lets the most units carry a low item, and
+/// that count. `None` when `n` units don't fit.
+fn weapon_split(two: &Pool, one: &Pool, n: usize) -> Option<(usize, usize)> {
+    let pairs = one.len() / 2;
+    (n.saturating_sub(pairs)..=n.min(two.len()))
+        .map(|b| (b, b.min(two.low.len()) + (n - b).min(one.low.len())))
+        .max_by_key(|&(_, covered)| covered)
+}
 
-    amulet: Option<&'a Item>,
-    belt: Option<&'a Item>,
-    gloves: Option<&'a Item>,
-    boots: Option<&'a Item>,
-    helmet: Option<&'a Item>,
-    body: Option<&'a Item>,
+/// Hand out units: the `lows` to consecutive sets from `next` (each of
+/// those sets now has its low item), the `rest` to every other set.
+fn place(sets: &mut [Vec<usize>], next: &mut usize, lows: Vec<Vec<usize>>, rest: Vec<Vec<usize>>) {
+    let covered = *next..*next + lows.len();
+    for (s, unit) in covered.clone().zip(lows) {
+        sets[s].extend(unit);
+    }
+    let others = (0..sets.len()).filter(|s| !covered.contains(s));
+    for (s, unit) in others.zip(rest) {
+        sets[s].extend(unit);
+    }
+    *next = covered.end;
+}
+
+/// Pair `lows` with partners from the front of `rest`, then pair off what
+/// is left.
+fn pairs(lows: Vec<usize>, rest: Vec<usize>) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+    let k = lows.len();
+    let low_units = lows
+        .into_iter()
+        .zip(&rest)
+        .map(|(l, &r)| vec![l, r])
+        .collect();
+    (low_units, rest[k..].chunks(2).map(|c| c.to_vec()).collect())
+}
+
+fn singles(v: Vec<usize>) -> Vec<Vec<usize>> {
+    v.into_iter().map(|i| vec![i]).collect()
 }
 
 impl StashAPIResult {
-    fn create_item_list<'a>(&'a mut self) -> ItemList<'a> {
-        let mut il = ItemList::default();
-        for item in self.items.iter_mut() {
-            let ty = item.get_category();
-            if ty == ItemType::Unknown {
+    /// Plan as many complete `mode` sets as the unused items allow.
+    ///
+    /// Slots are filled from per-slot pools; the set count is the largest
+    /// `n` every slot can supply (two rings, and either one two-hander or
+    /// two one-handers per set) for which, in chaos mode, enough ilvl
+    /// 60–74 items exist across slots to give every set one. Low items
+    /// are spent one per set; unidentified items are taken first and land
+    /// in the earliest sets, so identified leftovers spoil as few sets as
+    /// possible. Each set is returned in click order (by tab, then slot).
+    fn plan(&self, mode: RecipeMode) -> Vec<Vec<usize>> {
+        let mut pools: [Pool; 8] = Default::default();
+        let mut two_hand = Pool::default();
+        let mut order: Vec<usize> = (0..self.items.len())
+            .filter(|&i| !self.items[i].used && self.items[i].eligible(mode))
+            .collect();
+        order.sort_by_key(|&i| self.items[i].identified);
+        for i in order {
+            let item = &self.items[i];
+            let Some(slot) = item.get_category().slot() else {
                 continue;
+            };
+            let pool = match (slot, item.weapon_fit()) {
+                (WEAPON, Some(WeaponFit::TwoHand)) => &mut two_hand,
+                (WEAPON, None) => continue,
+                _ => &mut pools[slot],
+            };
+            if item.is_low() {
+                pool.low.push(i);
+            } else {
+                pool.high.push(i);
             }
+        }
 
-            if item.used {
-                continue;
-            }
+        let chaos = mode == RecipeMode::Chaos;
+        let upper = pools[..RING]
+            .iter()
+            .map(Pool::len)
+            .chain([
+                pools[RING].len() / 2,
+                two_hand.len() + pools[WEAPON].len() / 2,
+            ])
+            .min()
+            .unwrap_or(0);
+        let feasible = |n: usize| {
+            let Some((_, weapon_low)) = weapon_split(&two_hand, &pools[WEAPON], n) else {
+                return false;
+            };
+            let covered: usize = pools[..=RING].iter().map(|p| p.low.len().min(n)).sum();
+            !chaos || covered + weapon_low >= n
+        };
+        let n = (0..=upper).rev().find(|&n| feasible(n)).unwrap_or(0);
+        if n == 0 {
+            return Vec::new();
+        }
 
-            item.used = true;
-            if ty == ItemType::Weapon && il.weapon1.is_none() {
-                il.weapon1 = Some(&*item);
-                continue;
-            }
+        let mut sets = vec![Vec::new(); n];
+        let mut need = if chaos { n } else { 0 };
+        let mut next = 0;
+        for pool in &pools[..RING] {
+            let k = need.min(pool.low.len()).min(n);
+            let (lows, rest) = pool.take(n, k);
+            place(&mut sets, &mut next, singles(lows), singles(rest));
+            need -= k;
+        }
 
-            if ty == ItemType::Weapon && il.weapon2.is_none() && item.h <= 3 {
-                il.weapon2 = Some(item);
-                continue;
-            }
+        let rings = &pools[RING];
+        let k = need.min(rings.low.len()).min(n);
+        let (lows, rest) = rings.take(2 * n, k);
+        let (low_units, rest_units) = pairs(lows, rest);
+        place(&mut sets, &mut next, low_units, rest_units);
+        need -= k;
 
-            if ty == ItemType::Ring && il.ring1.is_none() {
-                il.ring1 = Some(item);
-                continue;
-            }
+        let one_hand = &pools[WEAPON];
+        let (b, _) = weapon_split(&two_hand, one_hand, n).expect("checked by feasible");
+        let kb = need.min(two_hand.low.len()).min(b);
+        let (two_low, two_rest) = two_hand.take(b, kb);
+        let ks = (need - kb).min(one_hand.low.len()).min(n - b);
+        let (one_low, one_rest) = one_hand.take(2 * (n - b), ks);
+        let (mut low_units, one_units) = pairs(one_low, one_rest);
+        low_units.splice(0..0, singles(two_low));
+        let mut rest_units = singles(two_rest);
+        rest_units.extend(one_units);
+        place(&mut sets, &mut next, low_units, rest_units);
 
-            if ty == ItemType::Ring && il.ring2.is_none() {
-                il.ring2 = Some(item);
-                continue;
-            }
+        for set in &mut sets {
+            set.sort_by_key(|&i| (self.items[i].tab, self.items[i].get_category().slot()));
+        }
+        sets
+    }
 
-            if ty == ItemType::Amulet && il.amulet.is_none() {
-                il.amulet = Some(item);
-                continue;
-            }
+    /// How many complete `mode` sets the tabs hold, and how many of those
+    /// are fully unidentified.
+    fn complete_sets(&self, mode: RecipeMode) -> (usize, usize) {
+        let sets = self.plan(mode);
+        let unid = sets
+            .iter()
+            .filter(|s| s.iter().all(|&i| !self.items[i].identified))
+            .count();
+        (sets.len(), unid)
+    }
 
-            if ty == ItemType::Belt && il.belt.is_none() {
-                il.belt = Some(item);
-                continue;
+    /// One set's clicks grouped by tab, in the order they are made.
+    fn click_plan(&self, set: &[usize]) -> Vec<(usize, Vec<&Item>)> {
+        let mut plan: Vec<(usize, Vec<&Item>)> = Vec::new();
+        for &i in set {
+            let item = &self.items[i];
+            match plan.last_mut() {
+                Some((tab, items)) if *tab == item.tab => items.push(item),
+                _ => plan.push((item.tab, vec![item])),
             }
+        }
+        plan
+    }
 
-            if ty == ItemType::Gloves && il.gloves.is_none() {
-                il.gloves = Some(item);
-                continue;
-            }
-            if ty == ItemType::Boots && il.boots.is_none() {
-                il.boots = Some(item);
-                continue;
-            }
-            if ty == ItemType::Helmet && il.helmet.is_none() {
-                il.helmet = Some(item);
-                continue;
-            }
-            if ty == ItemType::Body && il.body.is_none() {
-                il.body = Some(item);
-                continue;
+    /// Per-slot progress toward complete `mode` sets, in `SLOT_NAMES`
+    /// order: items for single slots, ring pairs, and weapon units (a
+    /// two-hander or a pair of one-handers). Ignores the chaos low-item
+    /// rule; that is about which items, not how many.
+    fn set_counts(&self, mode: RecipeMode) -> [usize; 8] {
+        let mut counts = [0; 8];
+        let mut one_hand = 0;
+        for item in self.items.iter().filter(|i| !i.used && i.eligible(mode)) {
+            match item.get_category().slot() {
+                Some(WEAPON) => match item.weapon_fit() {
+                    Some(WeaponFit::TwoHand) => counts[WEAPON] += 1,
+                    Some(WeaponFit::OneHand) => one_hand += 1,
+                    None => {}
+                },
+                Some(slot) => counts[slot] += 1,
+                None => {}
             }
-
-            item.used = false;
         }
+        counts[RING] /= 2;
+        counts[WEAPON] += one_hand / 2;
+        counts
+    }
 
-        il
+    fn tab_name(&self, tab: usize) -> &str {
+        self.fetched.get(tab).map(String::as_str).unwrap_or("")
     }
 
-    fn tally(&self) -> ItemCount {
+    /// Items per category that are eligible for `mode`; everything else
+    /// (including non-rares) counts as `other`.
+    fn tally(&self, mode: RecipeMode) -> ItemCount {
         let mut ic = ItemCount {
             weapon: 0,
             ring: 0,
@@ -321,7 +886,11 @@ impl StashAPIResult {
         };
 
         for item in &self.items {
-            let ty = item.get_category();
+            let ty = if item.eligible(mode) {
+                item.get_category()
+            } else {
+                ItemType::Unknown
+            };
             let field = match ty {
                 ItemType::Weapon => &mut ic.weapon,
                 ItemType::Ring => &mut ic.ring,
@@ -338,100 +907,244 @@ impl StashAPIResult {
 
         ic
     }
-}
-
-impl ItemList<'_> {
-    fn take(&self, app: &crate::App) {
-        let (delay, grid, frame) = {
-            let settings = app.settings.read();
-            let grid = match &settings.stash_grid {
-                Some(g) => g.clone(),
-                None => {
-                    println!("Stash grid not calibrated — run: little_oil calibrate-stash");
-                    return;
-                }
-            };
-            match settings.screenshot() {
-                Ok(f) => (settings.pull_delay, grid, f),
-                Err(e) => {
-                    println!("Could not screenshot: {e}");
-                    return;
-                }
-            }
-        };
 
-        let click_quad = |x: usize, y: usize| {
-            if x >= 24 || y >= 24 {
-                println!("Item slot ({x}, {y}) is outside the 24x24 grid, skipping");
-                return;
-            }
-            let (px, py) = grid.cell_center(x, y);
-            let (sx, sy) = frame.frame_to_screen(px, py);
-            app.click(sx, sy);
-            std::thread::sleep(std::time::Duration::from_millis(delay + 10));
-        };
-
-        let clicks = [
-            ("Body", self.body),
-            ("Helmet", self.helmet),
-            ("Boots", self.boots),
-            ("Gloves", self.gloves),
-            ("Belt", self.belt),
-            ("Weapon A", self.weapon1),
-            ("Weapon B", self.weapon2),
-            ("Ring 1", self.ring1),
-            ("Ring 2", self.ring2),
-            ("Amulet", self.amulet),
-        ];
+    /// Whether fetched tab `tab` is a quad tab, from its resolved type (the
+    /// response's `quadLayout` for the first tab when it is missing). Any
+    /// other tab of unknown type is an error rather than a guess.
+    fn is_quad(&self, tab: usize) -> anyhow::Result<bool> {
+        match self.fetched_tabs.get(tab).map(|t| t.kind.as_str()) {
+            Some("QuadStash") => Ok(true),
+            Some(k) if !k.is_empty() => Ok(false),
+            _ if tab == 0 => Ok(self.quad_layout),
+            _ => anyhow::bail!(
+                "Stash tab type of '{}' unknown — fetch it again without --stash-file, or check it with: little_oil tabs",
+                self.tab_name(tab)
+            ),
+        }
+    }
 
+    /// Click one planned set out of the stash, switching tabs through the
+    /// `tab-<name>` points when the recipe spans more than one tab. Each
+    /// tab is clicked through the grid for its layout.
+    fn take(
+        &self,
+        app: &crate::App,
+        set: &[usize],
+        current: &mut Option<usize>,
+    ) -> anyhow::Result<()> {
+        let delay = { app.settings.read().pull_delay };
         std::thread::sleep(std::time::Duration::from_millis(delay));
-        for (name, c) in clicks {
-            match c {
-                Some(s) => {
-                    println!("Got item (slot {}): {}", name, s.base_type);
-                    click_quad(s.x, s.y);
-                }
-                None => {
-                    println!("No item for slot {}", name);
-                }
+        for (tab, items) in self.click_plan(set) {
+            if self.fetched.len() > 1 && *current != Some(tab) {
+                println!("Switching to tab {}", self.tab_name(tab));
+                app.click_point(&format!("tab-{}", self.tab_name(tab)))?;
+                // The tab contents redraw after the click lands.
+                std::thread::sleep(std::time::Duration::from_millis(delay + 200));
+                *current = Some(tab);
+            }
+            let clicker = TabClicker::new(app, self.is_quad(tab)?)?;
+            for item in items {
+                let slot = item.get_category().slot().map_or("?", |s| SLOT_NAMES[s]);
+                println!("Got item (slot {}): {}", slot, item.base_type);
+                clicker.click(app, item.x, item.y);
             }
         }
+        Ok(())
+    }
+}
+
+/// Clicks stash cells by their stash API (x, y), through the calibrated
+/// grid for the tab's layout. Shared by every recipe that pulls items out
+/// of a tab.
+pub(crate) struct TabClicker {
+    delay: u64,
+    grid: crate::stash_grid::TabGrid,
+    frame: crate::screenshot::ScreenshotData,
+}
+
+impl TabClicker {
+    pub(crate) fn new(app: &crate::App, quad: bool) -> anyhow::Result<TabClicker> {
+        let settings = app.settings.read();
+        Ok(TabClicker {
+            delay: settings.pull_delay,
+            grid: settings.tab_grid(quad)?,
+            frame: settings.screenshot()?,
+        })
+    }
+
+    /// Click cell (x, y) and wait `pull_delay`. Cells outside the grid are
+    /// reported and skipped.
+    pub(crate) fn click(&self, app: &crate::App, x: usize, y: usize) {
+        let Some((px, py)) = self.grid.cell_center(x, y) else {
+            let (cols, rows) = self.grid.dims();
+            println!("Item slot ({x}, {y}) is outside the {cols}x{rows} grid, skipping");
+            return;
+        };
+        let (sx, sy) = self.frame.frame_to_screen(px, py);
+        app.click(sx, sy);
+        std::thread::sleep(std::time::Duration::from_millis(self.delay + 10));
     }
 }
 
-pub fn get_tally(app: &crate::App, cr_config: &ChaosRecipe) -> anyhow::Result<()> {
-    let apir = cr_config.get_json(app)?;
-    println!("Total item counts: {:?}", apir.tally());
+pub fn get_tally(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    source: &StashSource,
+    mode: RecipeMode,
+) -> anyhow::Result<()> {
+    let apir = cr_config.get_json(app, source)?;
+    println!("Eligible {mode:?} item counts: {:?}", apir.tally(mode));
+    for m in [RecipeMode::Chaos, RecipeMode::Regal] {
+        let (sets, unid) = apir.complete_sets(m);
+        println!("Complete {m:?} sets: {sets} ({unid} fully unidentified)");
+    }
     Ok(())
 }
 
-pub fn do_recipe(app: &crate::App, cr_config: &ChaosRecipe, amt: usize) -> anyhow::Result<()> {
-    let mut apir = cr_config.get_json(app)?;
-    for _i in 0..amt {
-        let item_list = apir.create_item_list();
-        item_list.take(app);
+/// Fetch the recipe tabs and report, per slot in `SLOT_NAMES` order,
+/// whether it holds fewer than `target` sets' worth of `mode` items.
+pub fn slots_under_target(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    source: &StashSource,
+    mode: RecipeMode,
+    target: usize,
+) -> anyhow::Result<[bool; 8]> {
+    let counts = cr_config.get_json(app, source)?.set_counts(mode);
+    Ok(counts.map(|c| c < target))
+}
+
+/// Pull up to `amt` planned sets. With `vend`, each set is sold through
+/// [`crate::App::vend`] before the stash is reopened via the `stash` point
+/// for the next one, so a single run turns several sets into currency.
+pub fn do_recipe(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    source: &StashSource,
+    amt: usize,
+    mode: RecipeMode,
+    vend: bool,
+) -> anyhow::Result<()> {
+    let apir = cr_config.get_json(app, source)?;
+    if apir.fetched.len() > 1 {
+        for name in &apir.fetched {
+            app.named_point(&format!("tab-{name}")).map_err(|_| {
+                anyhow::anyhow!(
+                    "Tab header for '{name}' not calibrated — run: little_oil calibrate-point tab-{name}"
+                )
+            })?;
+        }
+    }
+    if vend {
+        for name in [
+            "stash",
+            "vendor",
+            "vendor-sell",
+            "vendor-accept",
+            "vendor-offer",
+        ] {
+            app.named_point(name)?;
+        }
+    }
+    let sets = apir.plan(mode);
+    println!("{} complete {mode:?} set(s) available", sets.len());
+    let mut current = None;
+    for (i, set) in sets.iter().take(amt).enumerate() {
+        if vend && i > 0 {
+            app.click_point("stash")?;
+            // The stash reopens on the tab it was closed on; give it time to draw.
+            let delay = { app.settings.read().pull_delay };
+            std::thread::sleep(std::time::Duration::from_millis(delay + 800));
+        }
+        if set.iter().all(|&j| !apir.items[j].identified) {
+            println!("Set {} is fully unidentified (double yield)", i + 1);
+        }
+        apir.take(app, set, &mut current)?;
+        if vend {
+            app.vend()?;
+        }
+    }
+    if sets.len() < amt {
+        println!("No complete {mode:?} set left after {} set(s)", sets.len());
     }
     Ok(())
 }
 
+/// `tabs [name ...]`: list every stash tab (index, type, colour, item count,
+/// `*` for recipe tabs) and save the recipe tabs' ids by name. Names given
+/// become the recipe tabs, the first one `tab_name`.
+pub fn list_tabs(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    select: &[String],
+) -> anyhow::Result<()> {
+    let catalogue = cr_config.catalogue()?;
+    let mut cr = cr_config.clone();
+    if let Some((first, rest)) = select.split_first() {
+        cr.tab_name = first.clone();
+        cr.tab_names = rest.to_vec();
+    }
+    let all: Vec<StashTab> = catalogue.iter().map(|(t, _)| t.clone()).collect();
+    let names = cr.tab_list();
+    let found = names
+        .iter()
+        .map(|n| {
+            cr.resolve(&all, n)
+                .cloned()
+                .ok_or_else(|| cr.missing_tab(n))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    println!(
+        " {:>3}  {:<24} {:<12} {:<9} {:>5}",
+        "idx", "name", "type", "colour", "items"
+    );
+    for (tab, count) in &catalogue {
+        let mark = if found.iter().any(|t| t.id == tab.id) {
+            '*'
+        } else {
+            ' '
+        };
+        println!(
+            "{mark}{:>3}  {:<24} {:<12} {} {:>5}",
+            tab.i,
+            tab.n,
+            tab.kind_label(),
+            tab.colour.swatch(),
+            count
+        );
+    }
+
+    if !select.is_empty() {
+        let mut settings = app.settings.write();
+        if let Some(s) = settings.chaos_recipe_settings.as_mut() {
+            s.tab_name = cr.tab_name.clone();
+            s.tab_names = cr.tab_names.clone();
+        }
+        crate::save_config(&crate::config_path()?, &settings.to_disk()?)?;
+        println!("Recipe tabs are now: {}", names.join(", "));
+    }
+    cr.remember_tabs(app, &names, &found)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{MockResponse, mock_http};
 
-    /// Inline PoE stash-API fixture (no network). Item order matters: the tall
-    /// 2x4 branch comes first so it becomes weapon1, then the 1x3 sword must
-    /// fit the weapon2 slot.
+    /// Inline PoE stash-API fixture (no network). The 2x4 branch is a
+    /// two-hander; the 1x3 sword needs a second one-hander to make a set.
     const FIXTURE: &str = r#"{
         "numTabs": 1,
         "quadLayout": true,
         "tabs": [],
         "items": [
-            {"x": 0, "y": 0, "identified": true, "baseType": "Iron Greaves", "ilvl": 1, "name": "", "typeLine": "", "w": 2, "h": 2, "properties": null},
-            {"x": 2, "y": 0, "identified": true, "baseType": "Two-Stone Ring", "ilvl": 1, "name": "", "typeLine": "", "w": 1, "h": 1, "properties": null},
-            {"x": 3, "y": 0, "identified": true, "baseType": "Iron Ring", "ilvl": 1, "name": "", "typeLine": "", "w": 1, "h": 1, "properties": null},
-            {"x": 4, "y": 0, "identified": true, "baseType": "Plate Vest", "ilvl": 1, "name": "", "typeLine": "", "w": 2, "h": 3, "properties": null},
-            {"x": 6, "y": 0, "identified": true, "baseType": "Gnarled Branch", "ilvl": 1, "name": "", "typeLine": "", "w": 2, "h": 4, "properties": [{"name": "Attacks per Second"}]},
-            {"x": 8, "y": 0, "identified": true, "baseType": "Rusted Sword", "ilvl": 1, "name": "", "typeLine": "", "w": 1, "h": 3, "properties": [{"name": "Attacks per Second"}]}
+            {"x": 0, "y": 0, "identified": true, "frameType": 2, "baseType": "Iron Greaves", "ilvl": 70, "name": "", "typeLine": "", "w": 2, "h": 2, "properties": null},
+            {"x": 2, "y": 0, "identified": true, "frameType": 2, "baseType": "Two-Stone Ring", "ilvl": 80, "name": "", "typeLine": "", "w": 1, "h": 1, "properties": null},
+            {"x": 3, "y": 0, "identified": true, "frameType": 2, "baseType": "Iron Ring", "ilvl": 80, "name": "", "typeLine": "", "w": 1, "h": 1, "properties": null},
+            {"x": 4, "y": 0, "identified": true, "frameType": 2, "baseType": "Plate Vest", "ilvl": 80, "name": "", "typeLine": "", "w": 2, "h": 3, "properties": null},
+            {"x": 6, "y": 0, "identified": true, "frameType": 2, "baseType": "Gnarled Branch", "ilvl": 80, "name": "", "typeLine": "", "w": 2, "h": 4, "properties": [{"name": "Attacks per Second"}]},
+            {"x": 8, "y": 0, "identified": true, "frameType": 2, "baseType": "Rusted Sword", "ilvl": 80, "name": "", "typeLine": "", "w": 1, "h": 3, "properties": [{"name": "Attacks per Second"}]}
         ]
     }"#;
 
@@ -441,7 +1154,7 @@ mod tests {
 
     #[test]
     fn tally_counts_by_category() {
-        let ic = fixture().tally();
+        let ic = fixture().tally(RecipeMode::Chaos);
         assert_eq!(ic.boots, 1);
         assert_eq!(ic.ring, 2);
         assert_eq!(ic.body, 1);
@@ -450,29 +1163,356 @@ mod tests {
     }
 
     #[test]
-    fn create_item_list_respects_weapon_height() {
-        let mut apir = fixture();
-        let il = apir.create_item_list();
-        let w2 = il.weapon2.expect("1x3 sword must fit the weapon2 slot");
-        assert_eq!(w2.base_type, "Rusted Sword");
+    fn oauth_fetch_resolves_tabs_by_name() {
+        let items = &FIXTURE[FIXTURE.find("\"items\"").unwrap()..];
+        let tab_body: &'static str = Box::leak(
+            format!(r#"{{"stash": {{"id": "d4e5f6", "name": "chaos", "type": "QuadStash", "index": 3, {items}}}"#)
+                .into_boxed_str(),
+        );
+        let (base, requests) = mock_http(vec![
+            MockResponse::ok(
+                r#"{"stashes": [
+                    {"id": "a1b2c3", "name": "dump", "type": "PremiumStash", "index": 0, "metadata": {}},
+                    {"id": "d4e5f6", "name": "chaos", "type": "QuadStash", "index": 3, "metadata": {"colour": "7c5436"}}
+                ]}"#,
+            ),
+            MockResponse::ok(tab_body),
+            MockResponse::ok(
+                r#"{"stash": {"id": "a1b2c3", "name": "dump", "type": "PremiumStash", "index": 0, "items": [
+                    {"x": 0, "y": 0, "identified": false, "frameType": 2, "baseType": "Iron Hat", "ilvl": 70, "name": "", "typeLine": "", "w": 2, "h": 2, "properties": null}
+                ]}}"#,
+            ),
+        ]);
+        let cr = ChaosRecipe {
+            session_id: String::new(),
+            account_name: String::new(),
+            league: "Standard".into(),
+            tab_name: "chaos".into(),
+            tab_index: Some(0),
+            tab_names: vec!["dump".into(), "chaos".into()],
+            tab_ids: BTreeMap::new(),
+            oauth_token: Some("tok".into()),
+            api_base: Some(base),
+        };
+        let (apir, found) = cr.fetch_oauth("tok").unwrap();
+        assert_eq!(found[0].i, 3);
+        assert_eq!(found[1].kind_label(), "premium");
+        assert!(apir.quad_layout);
+        assert_eq!(apir.tabs[1].colour.r, 0x7c);
+        assert_eq!(apir.fetched, ["chaos", "dump"]);
+        assert_eq!(apir.items.len(), 7);
+        assert_eq!(apir.items[6].tab, 1);
+        let mut expected = fixture().tally(RecipeMode::Chaos);
+        expected.helmet += 1;
+        assert_eq!(apir.tally(RecipeMode::Chaos), expected);
+        let seen = requests.lock();
+        assert!(seen[1].starts_with("GET /stash/Standard/d4e5f6 "));
+        assert!(seen[2].starts_with("GET /stash/Standard/a1b2c3 "));
+    }
+
+    #[test]
+    fn saved_tab_ids_survive_reordering() {
+        let tab = |n: &str, i, id: &str| StashTab {
+            n: n.into(),
+            i,
+            id: id.into(),
+            colour: Color { r: 0, g: 0, b: 0 },
+            kind: "QuadStash".into(),
+        };
+        let mut cr: ChaosRecipe =
+            serde_json::from_str(r#"{"league": "Standard", "tab_name": "chaos", "tab_index": 0}"#)
+                .unwrap();
+        let before = [tab("chaos", 0, "aa"), tab("dump", 1, "bb")];
+        assert_eq!(cr.resolve(&before, "chaos").unwrap().id, "aa");
+
+        cr.tab_ids.insert("chaos".into(), "aa".into());
+        // Moved to index 5 and renamed in game: the id still finds it, and a
+        // new tab now called "chaos" does not steal it.
+        let after = [
+            tab("chaos", 0, "cc"),
+            tab("dump", 1, "bb"),
+            tab("old chaos", 5, "aa"),
+        ];
+        assert_eq!(cr.resolve(&after, "chaos").unwrap().i, 5);
+        // Deleted: fall back to the name.
+        assert_eq!(cr.resolve(&after[..2], "chaos").unwrap().id, "cc");
+        assert_eq!(after[0].kind_label(), "quad");
     }
 
     #[test]
-    fn second_recipe_pass_picks_different_items() {
+    fn renamed_normal_tab_keeps_its_grid() {
+        // "dump" was renamed to "loot" in game; its saved id still finds it,
+        // and it is clicked through the normal grid, not the quad one.
+        let (base, _) = mock_http(vec![
+            MockResponse::ok(
+                r#"{"stashes": [
+                    {"id": "d4e5f6", "name": "chaos", "type": "QuadStash", "index": 0, "metadata": {}},
+                    {"id": "a1b2c3", "name": "loot", "type": "PremiumStash", "index": 1, "metadata": {}}
+                ]}"#,
+            ),
+            MockResponse::ok(
+                r#"{"stash": {"id": "d4e5f6", "name": "chaos", "type": "QuadStash", "items": []}}"#,
+            ),
+            MockResponse::ok(
+                r#"{"stash": {"id": "a1b2c3", "name": "loot", "type": "PremiumStash", "items": []}}"#,
+            ),
+        ]);
+        let mut cr: ChaosRecipe = serde_json::from_str(
+            r#"{"league": "Standard", "tab_name": "chaos", "tab_index": 0, "tab_names": ["dump"]}"#,
+        )
+        .unwrap();
+        cr.tab_ids.insert("dump".into(), "a1b2c3".into());
+        cr.oauth_token = Some("tok".into());
+        cr.api_base = Some(base);
+        let (mut apir, _) = cr.fetch_oauth("tok").unwrap();
+        assert!(apir.is_quad(0).unwrap());
+        assert!(!apir.is_quad(1).unwrap());
+
+        // A snapshot without resolved tabs gets them from its tab list.
+        apir.fetched_tabs.clear();
+        cr.resolve_fetched(&mut apir);
+        assert!(!apir.is_quad(1).unwrap());
+
+        // Unknown type is an error, never the quad grid by default.
+        apir.fetched_tabs.truncate(1);
+        assert!(apir.is_quad(1).is_err());
+    }
+
+    #[test]
+    fn cached_snapshot_keeps_tabs_and_counts() {
+        let tmp = crate::test_support::TempDir::new("chaos-cache");
         let mut apir = fixture();
-        assert!(apir.create_item_list().weapon1.is_some());
-        // Pass 1 marked every fixture item `used`, so pass 2 finds nothing.
-        let second = apir.create_item_list();
-        assert!(second.weapon1.is_none());
-        assert!(second.weapon2.is_none());
-        assert!(second.ring1.is_none());
-        assert!(second.ring2.is_none());
-        assert!(second.amulet.is_none());
-        assert!(second.belt.is_none());
-        assert!(second.gloves.is_none());
-        assert!(second.boots.is_none());
-        assert!(second.helmet.is_none());
-        assert!(second.body.is_none());
+        apir.items[0].tab = 1;
+        apir.fetched = vec!["chaos".into(), "dump".into()];
+        stash_cache::save(tmp.path(), "Standard", &["chaos", "dump"], &apir).unwrap();
+        let entry =
+            stash_cache::latest::<StashAPIResult>(tmp.path(), "Standard", &["chaos", "dump"])
+                .unwrap();
+        assert_eq!(entry.data.fetched, apir.fetched);
+        assert_eq!(entry.data.items[0].tab, 1);
+        assert_eq!(
+            entry.data.tally(RecipeMode::Chaos),
+            apir.tally(RecipeMode::Chaos)
+        );
+    }
+
+    fn item(base: &str, ilvl: usize, identified: bool) -> Item {
+        let (w, h) = match base {
+            "Gnarled Branch" => (2, 4),
+            "Rusted Sword" => (1, 3),
+            "Nailed Fist" => (2, 2),
+            "Rusted Spike" => (1, 4),
+            _ => (1, 1),
+        };
+        Item {
+            x: 0,
+            y: 0,
+            identified,
+            base_type: base.into(),
+            ilvl,
+            name: String::new(),
+            type_line: base.into(),
+            w,
+            h,
+            properties: (h > 1).then(|| vec![serde_json::json!({"name": "Attacks per Second"})]),
+            frame_type: FRAME_RARE,
+            tab: 0,
+            used: false,
+        }
+    }
+
+    /// Every armour and jewellery slot of one set, no weapon.
+    fn armour_set(ilvl: usize, identified: bool) -> Vec<Item> {
+        [
+            "Iron Ring",
+            "Iron Ring",
+            "Onyx Amulet",
+            "Leather Belt",
+            "Iron Gauntlets",
+            "Iron Greaves",
+            "Iron Hat",
+            "Plate Vest",
+        ]
+        .iter()
+        .map(|b| item(b, ilvl, identified))
+        .collect()
+    }
+
+    /// One full set: a two-handed branch plus `armour_set`.
+    fn full_set(ilvl: usize, identified: bool) -> Vec<Item> {
+        let mut items = armour_set(ilvl, identified);
+        items.push(item("Gnarled Branch", ilvl, identified));
+        items
+    }
+
+    fn stash(items: Vec<Item>) -> StashAPIResult {
+        StashAPIResult {
+            num_tabs: 1,
+            quad_layout: true,
+            items,
+            tabs: Vec::new(),
+            fetched: Vec::new(),
+            fetched_tabs: Vec::new(),
+        }
+    }
+
+    fn lows_per_set(apir: &StashAPIResult, mode: RecipeMode) -> Vec<usize> {
+        apir.plan(mode)
+            .iter()
+            .map(|set| set.iter().filter(|&&i| apir.items[i].is_low()).count())
+            .collect()
+    }
+
+    #[test]
+    fn only_rares_qualify() {
+        let mut items = full_set(70, true);
+        items[1].frame_type = 1;
+        let apir = stash(items);
+        assert!(apir.plan(RecipeMode::Chaos).is_empty());
+        assert_eq!(apir.tally(RecipeMode::Chaos).other, 1);
+    }
+
+    #[test]
+    fn chaos_needs_one_low_item_and_regal_none() {
+        assert_eq!(
+            stash(full_set(80, true)).complete_sets(RecipeMode::Chaos),
+            (0, 0)
+        );
+        assert_eq!(
+            stash(full_set(80, true)).complete_sets(RecipeMode::Regal),
+            (1, 0)
+        );
+        assert_eq!(
+            stash(full_set(70, true)).complete_sets(RecipeMode::Regal),
+            (0, 0)
+        );
+        assert_eq!(
+            stash(full_set(55, true)).complete_sets(RecipeMode::Chaos),
+            (0, 0)
+        );
+
+        // Two high sets and one low amulet: only one chaos set can be built.
+        let mut items = full_set(80, true);
+        items.extend(full_set(80, true));
+        items.push(item("Onyx Amulet", 65, true));
+        let apir = stash(items);
+        assert_eq!(apir.complete_sets(RecipeMode::Chaos), (1, 0));
+        assert_eq!(lows_per_set(&apir, RecipeMode::Chaos), [1]);
+    }
+
+    #[test]
+    fn low_items_are_spent_one_per_set() {
+        // One all-low set next to two high ones: the low items are spread
+        // so all three sets qualify, not kept together in one.
+        let mut items = full_set(70, true);
+        items.extend(full_set(80, true));
+        items.extend(full_set(80, true));
+        let lows = lows_per_set(&stash(items), RecipeMode::Chaos);
+        assert_eq!(lows.len(), 3);
+        assert!(lows.iter().all(|&n| n >= 1), "{lows:?}");
+
+        // Spare low amulets stay in the stash.
+        let mut items = full_set(80, true);
+        items.extend(full_set(80, true));
+        items.extend((0..3).map(|_| item("Onyx Amulet", 65, true)));
+        assert_eq!(lows_per_set(&stash(items), RecipeMode::Chaos), [1, 1]);
+    }
+
+    #[test]
+    fn unidentified_items_are_taken_first() {
+        let mut items = full_set(80, true);
+        items.extend(full_set(80, false));
+        items.push(item("Onyx Amulet", 65, false));
+        let apir = stash(items);
+        assert_eq!(apir.complete_sets(RecipeMode::Chaos), (1, 1));
+        let sets = apir.plan(RecipeMode::Chaos);
+        assert!(sets[0].iter().all(|&i| !apir.items[i].identified));
+    }
+
+    #[test]
+    fn weapons_are_one_two_hander_or_two_one_handers() {
+        let mut items = armour_set(80, true);
+        items.extend(armour_set(80, true));
+        items.push(item("Gnarled Branch", 80, true));
+        items.push(item("Rusted Sword", 80, true));
+        assert_eq!(
+            stash(items.clone()).complete_sets(RecipeMode::Regal),
+            (1, 0)
+        );
+
+        items.push(item("Rusted Sword", 80, true));
+        let apir = stash(items);
+        let sets = apir.plan(RecipeMode::Regal);
+        assert_eq!(sets.len(), 2);
+        let weapons: Vec<usize> = sets
+            .iter()
+            .map(|s| {
+                s.iter()
+                    .filter(|&&i| apir.items[i].get_category() == ItemType::Weapon)
+                    .count()
+            })
+            .collect();
+        assert_eq!(weapons, [1, 2]);
+
+        // The fixture's branch and sword alone make no set.
+        assert!(fixture().plan(RecipeMode::Chaos).is_empty());
+    }
+
+    #[test]
+    fn set_counts_pair_rings_and_one_handers() {
+        let mut items = full_set(80, true);
+        items.push(item("Rusted Sword", 80, true));
+        items.push(item("Rusted Sword", 80, true));
+        items.push(item("Iron Ring", 80, true));
+        assert_eq!(
+            stash(items).set_counts(RecipeMode::Chaos),
+            [1, 1, 1, 1, 1, 1, 1, 2]
+        );
+    }
+
+    #[test]
+    fn big_one_handers_never_fill_the_weapon_slot() {
+        // A claw (2x2) or a thrusting sword (1x4) is a one-hander: alone it
+        // is not a weapon unit, and it does not fit a 1x3 pair either.
+        for base in ["Nailed Fist", "Rusted Spike"] {
+            let mut items = armour_set(80, true);
+            items.push(item(base, 80, true));
+            items.push(item(base, 80, true));
+            let apir = stash(items);
+            assert!(apir.plan(RecipeMode::Regal).is_empty(), "{base}");
+            assert_eq!(apir.set_counts(RecipeMode::Regal)[WEAPON], 0, "{base}");
+
+            // The 1x3 swords beside them make the set, without them.
+            let mut items = armour_set(80, true);
+            items.push(item(base, 80, true));
+            items.push(item("Rusted Sword", 80, true));
+            items.push(item("Rusted Sword", 80, true));
+            let apir = stash(items);
+            let sets = apir.plan(RecipeMode::Regal);
+            assert_eq!(sets.len(), 1, "{base}");
+            assert!(sets[0].iter().all(|&i| apir.items[i].base_type != base));
+        }
+    }
+
+    #[test]
+    fn planned_sets_are_disjoint_and_grouped_by_tab() {
+        let mut items = full_set(70, false);
+        items.extend(full_set(80, true).into_iter().map(|i| Item { tab: 1, ..i }));
+        items.extend(full_set(80, true));
+        let mut apir = stash(items);
+        apir.fetched = vec!["chaos".into(), "dump".into()];
+        let sets = apir.plan(RecipeMode::Chaos);
+        assert_eq!(sets.len(), 3);
+        let mut all: Vec<usize> = sets.concat();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 27);
+        for set in &sets {
+            let plan = apir.click_plan(set);
+            let tabs: Vec<usize> = plan.iter().map(|(t, _)| *t).collect();
+            assert!(tabs.windows(2).all(|w| w[0] < w[1]), "{tabs:?}");
+            assert_eq!(plan.iter().map(|(_, v)| v.len()).sum::<usize>(), 9);
+        }
     }
 }
 //curl 'https://www.pathofexile.com/character-window/get-stash-items
@@ -0,0 +1,123 @@
+//! Tolerant colour comparison for every pixel check (empty inventory slots,
+//! search highlights, screen diffs).
+//!
+//! Colours are compared by their largest per-channel difference, ignoring
+//! alpha. A pixel matches a calibrated colour when that distance is within
+//! the configured `colour_tolerance` plus the colour's own spread — how far
+//! the calibration reads of it strayed from their mean (capture noise,
+//! dithering, scaling). Gamma, HDR tone mapping or a brightness change then
+//! shift every read a little instead of breaking exact equality.
+/// Default `colour_tolerance`: max per-channel difference on top of a
+/// sample's spread.
+pub const DEFAULT_TOLERANCE: u8 = 12;
+
+/// (r, g, b) of a colour packed by `ScreenshotData::get_pixel`.
+pub fn channels(c: u32) -> [u8; 3] {
+    [(c >> 24) as u8, (c >> 16) as u8, (c >> 8) as u8]
+}
+
+/// Largest per-channel difference between two colours.
+pub fn distance(a: u32, b: u32) -> u8 {
+    let (a, b) = (channels(a), channels(b));
+    (0..3).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matcher {
+    pub tolerance: u8,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Matcher {
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+impl Matcher {
+    /// Whether `actual` reads as `expected`, a calibrated colour whose reads
+    /// spread by `spread`.
+    pub fn matches(self, actual: u32, expected: u32, spread: u8) -> bool {
+        distance(actual, expected) <= self.tolerance.saturating_add(spread)
+    }
+
+    /// Whether two captures of the same pixel differ.
+    pub fn differs(self, a: u32, b: u32) -> bool {
+        !self.matches(a, b, 0)
+    }
+}
+
+/// Calibrated three-probe colours per cell (the inventory's empty-slot
+/// samples) with their spreads.
+#[derive(Debug, Clone)]
+pub struct ProbeSamples {
+    pub colours: Vec<[u32; 3]>,
+    /// Per probe; missing for configs sampled before spreads were recorded.
+    pub spread: Vec<[u8; 3]>,
+    pub matcher: Matcher,
+}
+
+impl ProbeSamples {
+    /// How many of cell `idx`'s three probe `reads` match its samples.
+    pub fn matching(&self, idx: usize, reads: [Option<u32>; 3]) -> usize {
+        let spread = self.spread.get(idx).copied().unwrap_or_default();
+        (0..3)
+            .filter(|&i| {
+                reads[i].is_some_and(|c| self.matcher.matches(c, self.colours[idx][i], spread[i]))
+            })
+            .count()
+    }
+}
+
+/// Mean colour of `reads` (alpha opaque) and their spread: the largest
+/// per-channel distance of any read from that mean.
+pub fn stats(reads: &[u32]) -> (u32, u8) {
+    if reads.is_empty() {
+        return (0, 0);
+    }
+    let mut sum = [0u32; 3];
+    for &c in reads {
+        for (s, v) in sum.iter_mut().zip(channels(c)) {
+            *s += v as u32;
+        }
+    }
+    let n = reads.len() as u32;
+    let [r, g, b] = sum.map(|s| (s + n / 2) / n);
+    let mean = r << 24 | g << 16 | b << 8 | 0xFF;
+    let spread = reads.iter().map(|&c| distance(c, mean)).max().unwrap_or(0);
+    (mean, spread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_allows_tolerance_plus_spread() {
+        let m = Matcher { tolerance: 4 };
+        assert_eq!(distance(0x102030FF, 0x142A2000), 16);
+        assert!(m.matches(0x102030FF, 0x132232FF, 0));
+        assert!(!m.matches(0x102030FF, 0x182030FF, 0));
+        assert!(m.matches(0x102030FF, 0x182030FF, 4));
+        assert!(!m.differs(0x102030FF, 0x102034FF));
+    }
+
+    #[test]
+    fn probe_samples_count_matching_reads() {
+        let samples = ProbeSamples {
+            colours: vec![[0x202020FF; 3]],
+            spread: vec![[0, 0, 10]],
+            matcher: Matcher { tolerance: 2 },
+        };
+        let reads = [Some(0x212121FF), Some(0x262626FF), Some(0x2A2A2AFF)];
+        assert_eq!(samples.matching(0, reads), 2);
+        assert_eq!(samples.matching(0, [None, None, Some(0x202020FF)]), 1);
+    }
+
+    #[test]
+    fn stats_report_mean_and_worst_read() {
+        assert_eq!(stats(&[0x0A0A0AFF, 0x141414FF]), (0x0F0F0FFF, 5));
+        assert_eq!(stats(&[0x102030FF; 3]), (0x102030FF, 0));
+    }
+}
@@ -1,318 +0,0 @@
-//! Static dictionaries for item classification.
-pub const BOOTS: &[&str] = &[
-    "Iron Greaves",
-    "Steel Greaves",
-    "Basemetal Treads",
-    "Plated Greaves",
-    "Reinforced Greaves",
-    "Antique Greaves",
-    "Ancient Greaves",
-    "Darksteel Treads",
-    "Goliath Greaves",
-    "Vaal Greaves",
-    "Titan Greaves",
-    "Brimstone Treads",
-    "Rawhide Boots",
-    "Goathide Boots",
-    "Cloudwhisper Boots",
-    "Deerskin Boots",
-    "Nubuck Boots",
-    "Eelskin Boots",
-    "Sharkskin Boots",
-    "Windbreak Boots",
-    "Shagreen Boots",
-    "Stealth Boots",
-    "Slink Boots",
-    "Stormrider Boots",
-    "Wool Shoes",
-    "Velvet Slippers",
-    "Duskwalk Slippers",
-    "Silk Slippers",
-    "Scholar Boots",
-    "Satin Slippers",
-    "Samite Slippers",
-    "Nightwind Slippers",
-    "Conjurer Boots",
-    "Arcanist Slippers",
-    "Sorcerer Boots",
-    "Dreamquest Slippers",
-    "Leatherscale Boots",
-    "Ironscale Boots",
-    "Bronzescale Boots",
-    "Steelscale Boots",
-    "Serpentscale Boots",
-    "Wyrmscale Boots",
-    "Hydrascale Boots",
-    "Dragonscale Boots",
-    "Two-Toned Boots",
-    "Chain Boots",
-    "Ringmail Boots",
-    "Mesh Boots",
-    "Riveted Boots",
-    "Zealot Boots",
-    "Soldier Boots",
-    "Legion Boots",
-    "Crusader Boots",
-    "Two-Toned Boots",
-    "Wrapped Boots",
-    "Strapped Boots",
-    "Clasped Boots",
-    "Shackled Boots",
-    "Trapper Boots",
-    "Ambush Boots",
-    "Carnal Boots",
-    "Assassin's Boots",
-    "Murder Boots",
-    "Fugitive Boots",
-    "Two-Toned Boots",
-];
-
-pub const HELMETS: &[&str] = &[
-    "Iron Hat",
-    "Cone Helmet",
-    "Barbute Helmet",
-    "Close Helmet",
-    "Gladiator Helmet",
-    "Reaver Helmet",
-    "Siege Helmet",
-    "Samnite Helmet",
-    "Ezomyte Burgonet",
-    "Royal Burgonet",
-    "Eternal Burgonet",
-    "Leather Cap",
-    "Tricorne",
-    "Leather Hood",
-    "Wolf Pelt",
-    "Hunter Hood",
-    "Noble Tricorne",
-    "Ursine Pelt",
-    "Silken Hood",
-    "Sinner Tricorne",
-    "Lion Pelt",
-    "Vine Circlet",
-    "Iron Circlet",
-    "Torture Cage",
-    "Tribal Circlet",
-    "Bone Circlet",
-    "Lunaris Circlet",
-    "Steel Circlet",
-    "Necromancer Circlet",
-    "Solaris Circlet",
-    "Mind Cage",
-    "Hubris Circlet",
-    "Battered Helm",
-    "Sallet",
-    "Sorrow Mask",
-    "Visored Sallet",
-    "Gilded Sallet",
-    "Secutor Helm",
-    "Fencer Helm",
-    "Atonement Mask",
-    "Lacquered Helmet",
-    "Fluted Bascinet",
-    "Pig-Faced Bascinet",
-    "Nightmare Bascinet",
-    "Penitent Mask",
-    "Rusted Coif",
-    "Soldier Helmet",
-    "Imp Crown",
-    "Great Helmet",
-    "Crusader Helmet",
-    "Aventail Helmet",
-    "Zealot Helmet",
-    "Demon Crown",
-    "Great Crown",
-    "Magistrate Crown",
-    "Prophet Crown",
-    "Praetor Crown",
-    "Bone Helmet",
-    "Archdemon Crown",
-    "Scare Mask",
-    "Plague Mask",
-    "Gale Crown",
-    "Iron Mask",
-    "Festival Mask",
-    "Golden Mask",
-    "Raven Mask",
-    "Callous Mask",
-    "Winter Crown",
-    "Regicide Mask",
-    "Harlequin Mask",
-    "Vaal Mask",
-    "Deicide Mask",
-    "Blizzard Crown",
-];
-
-pub const GLOVES: &[&str] = &[
-    "Iron Gauntlets",
-    "Taxing Gauntlets",
-    "Plated Gauntlets",
-    "Bronze Gauntlets",
-    "Steel Gauntlets",
-    "Antique Gauntlets",
-    "Gruelling Gauntlets",
-    "Ancient Gauntlets",
-    "Goliath Gauntlets",
-    "Vaal Gauntlets",
-    "Titan Gauntlets",
-    "Debilitation Gauntlets",
-    "Spiked Gloves",
-    "Rawhide Gloves",
-    "Goathide Gloves",
-    "Gauche Gloves",
-    "Deerskin Gloves",
-    "Nubuck Gloves",
-    "Eelskin Gloves",
-    "Southswing Gloves",
-    "Sharkskin Gloves",
-    "Shagreen Gloves",
-    "Stealth Gloves",
-    "Gripped Gloves",
-    "Sinistral Gloves",
-    "Slink Gloves",
-    "Wool Gloves",
-    "Leyline Gloves",
-    "Velvet Gloves",
-    "Silk Gloves",
-    "Embroidered Gloves",
-    "Aetherwind Gloves",
-    "Satin Gloves",
-    "Samite Gloves",
-    "Conjurer Gloves",
-    "Arcanist Gloves",
-    "Sorcerer Gloves",
-    "Fingerless Silk Gloves",
-    "Nexus Gloves",
-    "Fishscale Gauntlets",
-    "Ironscale Gauntlets",
-    "Bronzescale Gauntlets",
-    "Steelscale Gauntlets",
-    "Serpentscale Gauntlets",
-    "Wyrmscale Gauntlets",
-    "Hydrascale Gauntlets",
-    "Dragonscale Gauntlets",
-    "Chain Gloves",
-    "Ringmail Gloves",
-    "Mesh Gloves",
-    "Riveted Gloves",
-    "Zealot Gloves",
-    "Soldier Gloves",
-    "Legion Gloves",
-    "Crusader Gloves",
-    "Apothecary's Gloves",
-    "Wrapped Mitts",
-    "Strapped Mitts",
-    "Clasped Mitts",
-    "Trapper Mitts",
-    "Ambush Mitts",
-    "Carnal Mitts",
-    "Assassin's Mitts",
-    "Murder Mitts",
-];
-
-pub const BODY: &[&str] = &[
-    "Plate Vest",
-    "Chestplate",
-    "Copper Plate",
-    "War Plate",
-    "Full Plate",
-    "Arena Plate",
-    "Lordly Plate",
-    "Bronze Plate",
-    "Battle Plate",
-    "Sun Plate",
-    "Colosseum Plate",
-    "Majestic Plate",
-    "Golden Plate",
-    "Crusader Plate",
-    "Astral Plate",
-    "Gladiator Plate",
-    "Glorious Plate",
-    "Shabby Jerkin",
-    "Strapped Leather",
-    "Buckskin Tunic",
-    "Wild Leather",
-    "Full Leather",
-    "Sun Leather",
-    "Thief's Garb",
-    "Eelskin Tunic",
-    "Frontier Leather",
-    "Glorious Leather",
-    "Coronal Leather",
-    "Cutthroat's Garb",
-    "Sharkskin Tunic",
-    "Destiny Leather",
-    "Exquisite Leather",
-    "Zodiac Leather",
-    "Assassin's Garb",
-    "Simple Robe",
-    "Silken Vest",
-    "Scholar's Robe",
-    "Silken Garb",
-    "Mage's Vestment",
-    "Silk Robe",
-    "Cabalist Regalia",
-    "Sage's Robe",
-    "Silken Wrap",
-    "Conjurer's Vestment",
-    "Spidersilk Robe",
-    "Destroyer Regalia",
-    "Savant's Robe",
-    "Necromancer Silks",
-    "Occultist's Vestment",
-    "Widowsilk Robe",
-    "Vaal Regalia",
-    "Scale Vest",
-    "Light Brigandine",
-    "Scale Doublet",
-    "Infantry Brigandine",
-    "Full Scale Armour",
-    "Soldier's Brigandine",
-    "Field Lamellar",
-    "Wyrmscale Doublet",
-    "Hussar Brigandine",
-    "Full Wyrmscale",
-    "Commander's Brigandine",
-    "Battle Lamellar",
-    "Dragonscale Doublet",
-    "Desert Brigandine",
-    "Full Dragonscale",
-    "General's Brigandine",
-    "Triumphant Lamellar",
-    "Chainmail Vest",
-    "Chainmail Tunic",
-    "Ringmail Coat",
-    "Chainmail Doublet",
-    "Full Ringmail",
-    "Full Chainmail",
-    "Holy Chainmail",
-    "Latticed Ringmail",
-    "Crusader Chainmail",
-    "Ornate Ringmail",
-    "Chain Hauberk",
-    "Devout Chainmail",
-    "Loricated Ringmail",
-    "Conquest Chainmail",
-    "Elegant Ringmail",
-    "Saint's Hauberk",
-    "Saintly Chainmail",
-    "Padded Vest",
-    "Oiled Vest",
-    "Padded Jacket",
-    "Oiled Coat",
-    "Scarlet Raiment",
-    "Waxed Garb",
-    "Bone Armour",
-    "Quilted Jacket",
-    "Sleek Coat",
-    "Crimson Raiment",
-    "Lacquered Garb",
-    "Crypt Armour",
-    "Sentinel Jacket",
-    "Varnished Coat",
-    "Blood Raiment",
-    "Sadist Garb",
-    "Carnal Armour",
-    "Grasping Mail",
-    "Sacrificial Garb",
-];
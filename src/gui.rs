@@ -11,7 +11,7 @@
 //!   inventory colors, calibrate pointer.
 //! * **Calibrate** — capture the game window, drag a rectangle, save it as a
 //!   region (game/inventory/stash/map), re-sample inventory colors.
-//! * **Inventory** — capture + live occupied-slot overlay.
+//! * **Inventory** — capture + live occupied-slot overlay; click a slot to lock it.
 //! * **Health** — checkhealth-style diagnostics (also `little_oil doctor`).
 //! * **Settings** — edit region numbers, pointer scale, focus clicks.
 
@@ -83,6 +83,9 @@ struct SetupState {
     /// Wizard step a desktop capture was last auto-attempted for: one attempt
     /// per step entry, so a failing capture doesn't spam the log every frame.
     last_captured_step: Option<usize>,
+    /// Let auto-calibrate overwrite regions and grids that are already
+    /// calibrated, not just fill the missing ones.
+    replace_calibration: bool,
 }
 struct LittleOilGui {
     app: Arc<App>,
@@ -102,6 +105,8 @@ struct LittleOilGui {
     setup: SetupState,
     health_checks: Option<Vec<crate::health::Check>>,
     last_tab: &'static str,
+    /// Name typed into the Settings tab's "Save profile as" box.
+    profile_name: String,
 
     // Windows-only: system tray + global hotkeys. Kept here (the event-loop
     // thread) because both are !Send/!Sync on Windows.
@@ -134,9 +139,11 @@ impl LittleOilGui {
                 detected_window: None,
                 detect_tried: false,
                 last_captured_step: None,
+                replace_calibration: false,
             },
             health_checks: None,
             last_tab: tab,
+            profile_name: String::new(),
             #[cfg(target_os = "windows")]
             tray: None,
             #[cfg(target_os = "windows")]
@@ -256,12 +263,48 @@ impl LittleOilGui {
         ))
     }
 
+    /// Estimate the inventory, stash and map regions, the stash/map grids
+    /// and the currency points from the game window size, and persist them.
+    /// Only unset ones are filled unless the user ticked "replace
+    /// calibrated"; grids keep their own highlight colour and spread, and
+    /// named points already captured are kept.
+    fn auto_calibrate(&mut self) {
+        let replace = self.setup.replace_calibration;
+        let snapshot = {
+            let mut s = self.app.settings.write();
+            let Some(game) = s.game_window_region else {
+                drop(s);
+                self.push("auto-calibrate needs the game window region first");
+                return;
+            };
+            let highlight = s
+                .stash_grid
+                .as_ref()
+                .map_or(crate::layout::DEFAULT_HIGHLIGHT, |g| g.highlight_color);
+            crate::layout::derive(game, highlight).apply(&mut s, replace);
+            s.clone()
+        };
+        let path = config_path().unwrap_or_else(|_| "config.json".into());
+        match snapshot
+            .to_disk()
+            .and_then(|d| save_config(&path, &d).map_err(Into::into))
+        {
+            Ok(()) if replace => self.push(
+                "auto-calibrated inventory, stash, map and missing currency points from the window size",
+            ),
+            Ok(()) => self.push(
+                "auto-calibrated the missing regions, grids and currency points from the window size",
+            ),
+            Err(e) => self.push(format!("saved in memory but config write failed: {e:#}")),
+        }
+    }
+
     /// Write `region` into the settings field for `target` and persist.
     fn apply_selected_region(&mut self, target: RegionTarget, region: ScreenRegion, what: &str) {
         {
             let mut s = self.app.settings.write();
             match target {
-                RegionTarget::Game => s.game_window_region = Some(region),
+                RegionTarget::Game => s.set_game_window(region),
                 RegionTarget::Inventory => s.inv_region = Some(region),
                 RegionTarget::Stash => s.stash_region = Some(region),
                 RegionTarget::Map => s.map_region = Some(region),
@@ -269,7 +312,10 @@ impl LittleOilGui {
         }
         let settings_snapshot = self.app.settings.read().clone();
         let path = config_path().unwrap_or_else(|_| "config.json".into());
-        match save_config(&path, &settings_snapshot) {
+        match settings_snapshot
+            .to_disk()
+            .and_then(|d| save_config(&path, &d).map_err(Into::into))
+        {
             Ok(()) => self.push(format!(
                 "{what} → {} region: {}x{} at ({}, {})",
                 target.label(),
@@ -515,10 +561,16 @@ impl LittleOilGui {
                 self.capture_preview(ctx);
             }
             ui.checkbox(&mut self.show_inv_overlay, "overlay occupied slots")
-                .on_hover_text("Highlight slots detected as occupied");
+                .on_hover_text(
+                    "Highlight slots detected as occupied, and outline the items the last inventory macro clicked",
+                );
         });
+        if self.show_inv_overlay {
+            ui.label("Click a slot to lock or unlock it — locked slots (blue) are never emptied.");
+        }
+        let mut toggle = None;
         if let Some(prev) = &self.preview {
-            let (rect, _) = ui.allocate_exact_size(prev.shown, egui::Sense::hover());
+            let (rect, response) = ui.allocate_exact_size(prev.shown, egui::Sense::click());
             ui.painter().image(
                 prev.texture.id(),
                 rect,
@@ -527,10 +579,49 @@ impl LittleOilGui {
             );
             if self.show_inv_overlay {
                 self.draw_inv_overlay(ui, rect, prev);
+                if response.clicked() {
+                    toggle = response
+                        .interact_pointer_pos()
+                        .and_then(|p| self.inv_cell_at(rect, prev, p));
+                }
             }
         } else {
             ui.weak("No capture yet — click \"Capture\".");
         }
+        if let Some((col, row)) = toggle {
+            let locked = self.app.settings.write().toggle_inv_lock(col, row);
+            let snapshot = self.app.settings.read().clone();
+            let path = config_path().unwrap_or_else(|_| "config.json".into());
+            let state = if locked { "locked" } else { "unlocked" };
+            match snapshot
+                .to_disk()
+                .and_then(|d| save_config(&path, &d).map_err(Into::into))
+            {
+                Ok(()) => self.push(format!("inventory slot ({col}, {row}) {state}")),
+                Err(e) => self.push(format!("config write failed: {e:#}")),
+            }
+        }
+    }
+
+    /// Inventory (col, row) under a display-space point of the preview drawn
+    /// in `rect`, or None outside the calibrated inventory grid.
+    fn inv_cell_at(
+        &self,
+        rect: egui::Rect,
+        preview: &Preview,
+        p: egui::Pos2,
+    ) -> Option<(u32, u32)> {
+        let inv = self.app.settings.read().inv_region?;
+        let fx = (p.x - rect.min.x) / preview.shown.x * preview.data.width as f32;
+        let fy = (p.y - rect.min.y) / preview.shown.y * preview.data.height as f32;
+        let sx = preview.data.origin.0 as f32 + fx - inv.x as f32;
+        let sy = preview.data.origin.1 as f32 + fy - inv.y as f32;
+        if sx < 0.0 || sy < 0.0 || inv.width < 12 || inv.height < 5 {
+            return None;
+        }
+        let col = sx as u32 / (inv.width / 12);
+        let row = sy as u32 / (inv.height / 5);
+        (col < 12 && row < 5).then_some((col, row))
     }
 
     fn draw_inv_overlay(&self, ui: &egui::Ui, rect: egui::Rect, preview: &Preview) {
@@ -538,10 +629,10 @@ impl LittleOilGui {
         let Some(inv) = settings.inv_region else {
             return;
         };
-        let Some(samples) = settings.inv_samples.clone() else {
+        let Ok(samples) = settings.inv_empty_samples() else {
             return;
         };
-        if samples.len() != 60 {
+        if samples.colours.len() != 60 {
             return;
         }
         let frame = &preview.data;
@@ -571,23 +662,22 @@ impl LittleOilGui {
                 // occupied = fewer than 2 probe pixels match the sample
                 let matches = App::inv_probes(frame, inv, col, row)
                     .map(|probes| {
-                        probes
-                            .iter()
-                            .zip(samples[(col * 5 + row) as usize].iter())
-                            .filter(|(p, s)| {
-                                frame
-                                    .try_get_pixel(p.0, p.1)
-                                    .map(|px| px == **s)
-                                    .unwrap_or(false)
-                            })
-                            .count()
+                        let reads = probes.map(|(px, py)| frame.try_get_pixel(px, py));
+                        samples.matching((col * 5 + row) as usize, reads)
                     })
                     .unwrap_or(0);
                 let occupied = matches < 2;
+                let locked = settings.inv_locked().get((col * 5 + row) as usize) == Some(&true);
                 let p0 = to_disp(cell_x, cell_y);
                 let p1 = to_disp(cell_x + cell_w, cell_y + cell_h);
                 let cell = egui::Rect::from_two_pos(p0, p1);
-                if occupied {
+                if locked {
+                    ui.painter().rect_filled(
+                        cell,
+                        0.0,
+                        egui::Color32::from_rgba_unmultiplied(60, 120, 220, 110),
+                    );
+                } else if occupied {
                     ui.painter().rect_filled(
                         cell,
                         0.0,
@@ -605,6 +695,21 @@ impl LittleOilGui {
                 );
             }
         }
+        // Item footprints from the last macro that read the inventory, one
+        // box per click target.
+        for item in self.app.inv_items.lock().iter() {
+            let p0 = to_disp(rx + item.col * cell_w, ry + item.row * cell_h);
+            let p1 = to_disp(
+                rx + (item.col + item.w) * cell_w,
+                ry + (item.row + item.h) * cell_h,
+            );
+            ui.painter().rect_stroke(
+                egui::Rect::from_two_pos(p0, p1),
+                0.0,
+                egui::Stroke::new(2.0_f32, egui::Color32::from_rgb(240, 200, 60)),
+                egui::StrokeKind::Inside,
+            );
+        }
     }
 
     /// Whether the wizard's pointer-calibration step should be shown at all.
@@ -725,6 +830,22 @@ impl LittleOilGui {
                     {
                         self.setup.step = 2;
                     }
+                    if ui
+                        .add_enabled(game_set, egui::Button::new("Auto-calibrate"))
+                        .on_hover_text(
+                            "Compute the inventory, stash, map and currency geometry from the \
+                             window size and skip to color sampling",
+                        )
+                        .clicked()
+                    {
+                        self.auto_calibrate();
+                        self.setup.step = 3;
+                    }
+                    ui.checkbox(&mut self.setup.replace_calibration, "replace calibrated")
+                        .on_hover_text(
+                            "Also overwrite regions and grids you already calibrated \
+                             (highlight colors and captured points are kept)",
+                        );
                 });
             }
             2 => {
@@ -884,7 +1005,10 @@ impl LittleOilGui {
                         }
                         let snapshot = self.app.settings.read().clone();
                         let path = config_path().unwrap_or_else(|_| "config.json".into());
-                        if let Err(e) = save_config(&path, &snapshot) {
+                        if let Err(e) = snapshot
+                            .to_disk()
+                            .and_then(|d| save_config(&path, &d).map_err(Into::into))
+                        {
                             self.push(format!("config write failed: {e:#}"));
                         } else {
                             self.push("Setup complete — you can use the Actions tab now");
@@ -940,6 +1064,81 @@ impl LittleOilGui {
         }
     }
 
+    /// Profile picker: switch, save the live calibration under a name, or
+    /// delete the active profile. Same operations as `little_oil profile`.
+    fn ui_profiles(&mut self, ui: &mut egui::Ui) {
+        let (names, active) = {
+            let s = self.app.settings.read();
+            let names: Vec<String> = s.profiles.keys().cloned().collect();
+            (names, s.active_profile.clone())
+        };
+        let mut action: Option<(&'static str, String)> = None;
+        ui.horizontal(|ui| {
+            ui.label("Profile:");
+            egui::ComboBox::from_id_salt("profile_picker")
+                .selected_text(active.as_deref().unwrap_or("(none)"))
+                .show_ui(ui, |ui| {
+                    for name in &names {
+                        if ui
+                            .selectable_label(active.as_ref() == Some(name), name)
+                            .clicked()
+                            && active.as_ref() != Some(name)
+                        {
+                            action = Some(("use", name.clone()));
+                        }
+                    }
+                });
+            if let Some(name) = &active
+                && ui
+                    .button("Delete")
+                    .on_hover_text("Forget this profile; the live calibration stays")
+                    .clicked()
+            {
+                action = Some(("delete", name.clone()));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.profile_name).desired_width(140.0));
+            if ui
+                .add_enabled(
+                    !self.profile_name.trim().is_empty(),
+                    egui::Button::new("Save profile as"),
+                )
+                .on_hover_text("Store the current regions, grids, points and pointer scale")
+                .clicked()
+            {
+                action = Some(("save", self.profile_name.trim().to_string()));
+                self.profile_name.clear();
+            }
+        });
+        let Some((op, name)) = action else {
+            return;
+        };
+        let result = {
+            let mut s = self.app.settings.write();
+            match op {
+                "use" => s.use_profile(&name).map(|_| ()),
+                "delete" => s.delete_profile(&name),
+                _ => s.save_profile(&name),
+            }
+            .map(|()| s.clone())
+        };
+        match result {
+            Ok(snapshot) => {
+                let path = config_path().unwrap_or_else(|_| "config.json".into());
+                match snapshot
+                    .to_disk()
+                    .and_then(|d| save_config(&path, &d).map_err(Into::into))
+                {
+                    Ok(()) => self.push(format!("profile {op}: {name}")),
+                    Err(e) => self.push(format!("config write failed: {e:#}")),
+                }
+                self.status = self.summary_line();
+            }
+            Err(e) => self.push(format!("profile {op} failed: {e:#}")),
+        }
+    }
+
     fn ui_settings(&mut self, ui: &mut egui::Ui) {
         ui.heading("Settings");
         ui.label("Regions are in screen pixels. Calibrate them with the drag-select on the Calibrate tab instead of typing numbers.");
@@ -980,17 +1179,35 @@ impl LittleOilGui {
                         dirty = true;
                     }
                     ui.end_row();
+                    let mut tolerance = s.colour_tolerance;
+                    ui.label("Colour tolerance:");
+                    if ui
+                        .add(egui::DragValue::new(&mut tolerance).range(0..=64))
+                        .on_hover_text(
+                            "Largest per-channel difference still read as the calibrated colour; raise it if gamma or HDR shifts colours",
+                        )
+                        .changed()
+                    {
+                        s.colour_tolerance = tolerance;
+                        dirty = true;
+                    }
+                    ui.end_row();
                 });
         }
         if dirty {
             let settings_snapshot = self.app.settings.read().clone();
             let path = config_path().unwrap_or_else(|_| "config.json".into());
-            match save_config(&path, &settings_snapshot) {
+            match settings_snapshot
+                .to_disk()
+                .and_then(|d| save_config(&path, &d).map_err(Into::into))
+            {
                 Ok(()) => self.push("settings saved"),
                 Err(e) => self.push(format!("config write failed: {e:#}")),
             }
         }
         ui.add_space(6.0);
+        self.ui_profiles(ui);
+        ui.add_space(6.0);
         ui.label("Config file:");
         match config_path() {
             Ok(p) => {
@@ -213,6 +213,75 @@ pub fn run(app: &App) -> Vec<Check> {
         }
     }
 
+    // 6b. Calibrated geometry against the estimate from the window size.
+    if let Some(game) = s.game_window_region {
+        let layout = crate::layout::derive(game, crate::layout::DEFAULT_HIGHLIGHT);
+        let slack = crate::layout::tolerance(game);
+        for (name, calibrated, estimate) in [
+            ("layout-inv", s.inv_region, layout.inv_region),
+            ("layout-stash", s.stash_region, layout.stash_region),
+            ("layout-map", s.map_region, layout.map_region),
+        ] {
+            let Some(r) = calibrated else { continue };
+            let off = crate::layout::deviation(r, estimate);
+            checks.push(if off <= slack {
+                ok(
+                    name,
+                    format!("within {off} px of the {}p layout", game.height),
+                )
+            } else {
+                warn(
+                    name,
+                    format!(
+                        "{off} px from the {}p layout estimate {}x{} @ ({}, {}) — \
+                         re-drag it, or auto-calibrate in Setup",
+                        game.height, estimate.width, estimate.height, estimate.x, estimate.y
+                    ),
+                )
+            });
+        }
+        let captured = s.points.as_deref().unwrap_or_default();
+        let worst = layout
+            .points
+            .iter()
+            .filter_map(|est| {
+                let p = captured.iter().find(|p| p.name == est.name)?;
+                Some((crate::layout::deviation(p.region, est.region), &est.name))
+            })
+            .max();
+        if let Some((off, name)) = worst {
+            checks.push(if off <= slack {
+                ok(
+                    "layout-currency",
+                    format!("within {off} px of the {}p layout", game.height),
+                )
+            } else {
+                warn(
+                    "layout-currency",
+                    format!(
+                        "'{name}' is {off} px from the {}p layout estimate — \
+                         re-run calibrate-currency if clicks miss",
+                        game.height
+                    ),
+                )
+            });
+        }
+        if let Some(g) = &s.stash_grid {
+            let want = layout.stash_grid.cell_w;
+            checks.push(if g.cell_w.abs_diff(want) <= 1 {
+                ok("layout-grid", format!("quad cell {} px", g.cell_w))
+            } else {
+                warn(
+                    "layout-grid",
+                    format!(
+                        "quad cell {} px but {want} px expected at {}p — re-run calibrate-stash",
+                        g.cell_w, game.height
+                    ),
+                )
+            });
+        }
+    }
+
     // 7. Inventory color samples.
     match &s.inv_samples {
         Some(v) if v.len() == 60 => checks.push(ok("inv-samples", "60 slots sampled")),
@@ -0,0 +1,194 @@
+//! Inventory (and stash) items as footprints on the cell grid, so a
+//! multi-cell item is one click target instead of one per occupied cell.
+//!
+//! Pixel probes only say which cells are occupied, not where one item ends
+//! and the next begins, so [`merge`] reads tooltips: the first unclaimed cell
+//! of an item in column-major order is its top-left corner, and a known
+//! footprint (stackables and 1x1 classes, else the base from `base_types`)
+//! claims the cells it covers without hovering them. An item with an unknown
+//! footprint stays a 1x1 target per cell: identical tooltips side by side may
+//! be one item or several, so they are never joined.
+use crate::item::Item;
+
+pub const INV_COLS: u32 = 12;
+pub const INV_ROWS: u32 = 5;
+
+/// Item classes that always take a single cell.
+const SINGLE_CELL_CLASSES: [&str; 12] = [
+    "Rings",
+    "Amulets",
+    "Jewels",
+    "Abyss Jewels",
+    "Maps",
+    "Map Fragments",
+    "Misc Map Items",
+    "Divination Cards",
+    "Stackable Currency",
+    "Skill Gems",
+    "Support Skill Gems",
+    "Incubators",
+];
+
+/// One inventory item: top-left cell, size in cells, and its tooltip (None
+/// when it could not be read).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvItem {
+    pub col: u32,
+    pub row: u32,
+    pub w: u32,
+    pub h: u32,
+    pub text: Option<String>,
+}
+
+impl InvItem {
+    pub fn covers(&self, col: u32, row: u32) -> bool {
+        (self.col..self.col + self.w).contains(&col) && (self.row..self.row + self.h).contains(&row)
+    }
+}
+
+/// (w, h) of the item a tooltip describes, when the tooltip says.
+pub fn footprint(text: &str) -> Option<(u32, u32)> {
+    if crate::item::stack_size(text).is_some() {
+        return Some((1, 1));
+    }
+    let item = Item::from_str(text).ok()?;
+    if SINGLE_CELL_CLASSES.contains(&item.item_class) {
+        return Some((1, 1));
+    }
+    crate::base_types::lookup(item.base_name).map(|b| (b.w.into(), b.h.into()))
+}
+
+/// Split a re-scan's occupied `cells` against the items an earlier read
+/// found: items whose cells are all still occupied carry over as they are,
+/// and the occupied cells none of them cover are returned to be read again.
+pub fn carry_over(known: &[InvItem], cells: &[(u32, u32)]) -> (Vec<InvItem>, Vec<(u32, u32)>) {
+    let kept: Vec<InvItem> = known
+        .iter()
+        .filter(|it| {
+            (it.col..it.col + it.w)
+                .all(|col| (it.row..it.row + it.h).all(|row| cells.contains(&(col, row))))
+        })
+        .cloned()
+        .collect();
+    let changed = cells
+        .iter()
+        .copied()
+        .filter(|&(col, row)| !kept.iter().any(|it| it.covers(col, row)))
+        .collect();
+    (kept, changed)
+}
+
+/// Group occupied cells into items. `cells` are (col, row) in column-major
+/// order on a `dims` (cols, rows) grid; `read` hovers a cell and returns its
+/// tooltip. Footprints are clamped to the grid. Cells with an unknown
+/// footprint or an unreadable tooltip become 1x1 items.
+pub fn merge(
+    cells: &[(u32, u32)],
+    dims: (u32, u32),
+    mut read: impl FnMut(u32, u32) -> Option<String>,
+) -> Vec<InvItem> {
+    let mut items: Vec<InvItem> = Vec::new();
+    for &(col, row) in cells {
+        if items.iter().any(|it| it.covers(col, row)) {
+            continue;
+        }
+        let text = read(col, row);
+        let (w, h) = text.as_deref().and_then(footprint).unwrap_or((1, 1));
+        items.push(InvItem {
+            col,
+            row,
+            w: w.clamp(1, dims.0 - col),
+            h: h.clamp(1, dims.1 - row),
+            text,
+        });
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGALIA: &str =
+        "Item Class: Body Armours\nRarity: Normal\nVaal Regalia\n--------\nItem Level: 80";
+    const CHAOS: &str =
+        "Item Class: Stackable Currency\nRarity: Currency\nChaos Orb\n--------\nStack Size: 20/20";
+    const TOTEM: &str = "Item Class: Quest Items\nRarity: Normal\nStrange Totem";
+    const GEM: &str = "Item Class: Skill Gems\nRarity: Gem\nFireball\n--------\nLevel: 1";
+
+    #[test]
+    fn merge_claims_known_footprints_and_splits_unknown_ones() {
+        // Regalia (2x3) at (0,0); two full chaos stacks stacked at (2,0)
+        // and (2,1); two identical unknown items at (3,0) and (3,1).
+        let mut cells = Vec::new();
+        for col in 0..4 {
+            for row in 0..3 {
+                if col < 2 || row < 2 {
+                    cells.push((col, row));
+                }
+            }
+        }
+        let mut hovered = Vec::new();
+        let items = merge(&cells, (INV_COLS, INV_ROWS), |col, row| {
+            hovered.push((col, row));
+            let text = match col {
+                0 | 1 => REGALIA,
+                2 => CHAOS,
+                _ => TOTEM,
+            };
+            Some(text.to_string())
+        });
+
+        let boxes: Vec<_> = items.iter().map(|i| (i.col, i.row, i.w, i.h)).collect();
+        assert_eq!(
+            boxes,
+            [
+                (0, 0, 2, 3),
+                (2, 0, 1, 1),
+                (2, 1, 1, 1),
+                (3, 0, 1, 1),
+                (3, 1, 1, 1)
+            ]
+        );
+        // The regalia's other five cells were never hovered.
+        assert_eq!(hovered.iter().filter(|c| c.0 < 2).count(), 1);
+        assert_eq!(footprint(GEM), Some((1, 1)));
+    }
+
+    #[test]
+    fn carry_over_keeps_unmoved_items_and_rereads_the_rest() {
+        let item = |col, row, w, h| InvItem {
+            col,
+            row,
+            w,
+            h,
+            text: None,
+        };
+        // The regalia stayed; the ring at (2,0) moved and something new
+        // landed at (3,4); half the 1x2 at (4,0) is gone.
+        let known = [item(0, 0, 2, 3), item(2, 0, 1, 1), item(4, 0, 1, 2)];
+        let mut cells: Vec<(u32, u32)> = (0..2)
+            .flat_map(|col| (0..3).map(move |row| (col, row)))
+            .collect();
+        cells.extend([(3, 4), (4, 1)]);
+        let (kept, changed) = carry_over(&known, &cells);
+        assert_eq!(kept, [item(0, 0, 2, 3)]);
+        assert_eq!(changed, [(3, 4), (4, 1)]);
+    }
+
+    #[test]
+    fn unreadable_cells_stay_single_and_footprints_clamp() {
+        let items = merge(&[(11, 3), (11, 4)], (INV_COLS, INV_ROWS), |_, row| {
+            (row == 3).then(|| REGALIA.to_string())
+        });
+        assert_eq!((items[0].w, items[0].h), (1, 2));
+        assert_eq!(items.len(), 1);
+
+        let items = merge(&[(5, 0), (5, 1)], (INV_COLS, INV_ROWS), |_, _| None);
+        let cells: Vec<_> = items
+            .iter()
+            .map(|i| (i.row, i.w * i.h, i.text.is_none()))
+            .collect();
+        assert_eq!(cells, [(0, 1, true), (1, 1, true)]);
+    }
+}
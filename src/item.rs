@@ -9,6 +9,8 @@ use tracing::{debug, span, trace};
 
 #[derive(Debug)]
 pub struct Item<'a> {
+    /// `Item Class:` value, e.g. `Boots`, `Jewels`.
+    pub item_class: &'a str,
     pub base_name: &'a str,
     pub item_name: ItemName,
 
@@ -16,6 +18,9 @@ pub struct Item<'a> {
 
     pub ilvl: u8,
     pub sockets: &'a str,
+    /// False when the tooltip carries the `Unidentified` line.
+    pub identified: bool,
+    pub corrupted: bool,
 
     pub mods: Vec<ItemMod<'a>>,
 }
@@ -132,7 +137,8 @@ impl<'a> Item<'a> {
         let mut cur_parser_state = ItemParseSections::Class;
 
         let mut item_type = None;
-        let mut item_name = String::new();
+        let mut rarity = "";
+        let mut name_lines: Vec<&'a str> = Vec::new();
 
         let mut current_parsed_modline = None;
         let mut mods = vec![];
@@ -173,7 +179,11 @@ impl<'a> Item<'a> {
                         anyhow::bail!("expected 'Rarity', got '{left}'");
                     }
 
-                    let _ = res.name("right").context("right part of rarity line")?;
+                    rarity = res
+                        .name("right")
+                        .context("right part of rarity line")?
+                        .as_str()
+                        .trim();
 
                     debug!(?item_type);
                     cur_parser_state = ItemParseSections::Name;
@@ -185,10 +195,7 @@ impl<'a> Item<'a> {
                         continue;
                     }
 
-                    if !item_name.is_empty() {
-                        item_name.push('\n');
-                    }
-                    item_name.push_str(line);
+                    name_lines.push(line);
                 }
                 ItemParseSections::Stats => {
                     if line == "--------" {
@@ -232,18 +239,85 @@ impl<'a> Item<'a> {
             };
         }
 
-        // TODO
+        // Flat facts that can sit in any section.
+        let mut ilvl = 1;
+        let mut sockets = "";
+        let mut identified = true;
+        let mut corrupted = false;
+        for line in source.lines().map(str::trim) {
+            if let Some(v) = line.strip_prefix("Item Level:") {
+                ilvl = v.trim().parse().unwrap_or(ilvl);
+            } else if let Some(v) = line.strip_prefix("Sockets:") {
+                sockets = v.trim();
+            } else if line == "Unidentified" {
+                identified = false;
+            } else if line == "Corrupted" {
+                corrupted = true;
+            }
+        }
+
+        // Rare and unique names are two lines (name, base); an unidentified
+        // rare or unique shows only the base, so its name stays empty. Magic
+        // names wrap the base in their affixes on one line; the base-type
+        // database finds the base inside it. An unknown base keeps the
+        // whole line.
+        let (base_name, item_name) = match (rarity, name_lines.as_slice()) {
+            ("Rare", [name, base]) => (*base, ItemName::Rare(name.to_string())),
+            ("Unique", [name, base]) => (*base, ItemName::Unique(name.to_string())),
+            ("Magic", [line]) => match crate::base_types::find_in(line) {
+                Some((base, at)) => {
+                    let end = at + base.name.len();
+                    (
+                        &line[at..end],
+                        ItemName::Magic {
+                            prefix: line[..at].trim().to_string(),
+                            suffix: line[end..].trim().to_string(),
+                        },
+                    )
+                }
+                None => (
+                    *line,
+                    ItemName::Magic {
+                        prefix: String::new(),
+                        suffix: String::new(),
+                    },
+                ),
+            },
+            ("Rare", [base]) => (*base, ItemName::Rare(String::new())),
+            ("Unique", [base]) => (*base, ItemName::Unique(String::new())),
+            ("Normal", [base]) => (*base, ItemName::Normal),
+            (_, lines) => (
+                lines.last().copied().unwrap_or(""),
+                ItemName::Other(lines.join("\n")),
+            ),
+        };
+
         let item = Item {
-            base_name: "",
-            item_name: ItemName::Normal,
+            item_class: item_type.map(|m| m.as_str().trim()).unwrap_or(""),
+            base_name,
+            item_name,
             stats: vec![],
-            ilvl: 1,
-            sockets: "",
+            ilvl,
+            sockets,
+            identified,
+            corrupted,
             mods,
         };
         Ok(item)
     }
 
+    /// `Normal`, `Magic`, `Rare` or `Unique`, matching the tooltip's
+    /// `Rarity:` wording; `Other` for everything else (gems, currency).
+    pub fn rarity(&self) -> &str {
+        match &self.item_name {
+            ItemName::Normal => "Normal",
+            ItemName::Magic { .. } => "Magic",
+            ItemName::Rare(_) => "Rare",
+            ItemName::Unique(_) => "Unique",
+            ItemName::Other(_) => "Other",
+        }
+    }
+
     pub fn num_mods(&self) -> (usize, usize) {
         let mut prefixes = 0;
         let mut suffixes = 0;
@@ -261,6 +335,32 @@ impl<'a> Item<'a> {
     }
 }
 
+/// The tooltip's name block (the lines between `Rarity:` and the first
+/// separator) joined with spaces, e.g. `Doom Knot Iron Ring`. Empty when the
+/// text is not a tooltip.
+pub fn display_name(source: &str) -> String {
+    source
+        .trim()
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| !l.starts_with("Rarity:"))
+        .skip(1)
+        .take_while(|l| *l != "--------")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `Stack Size: 9/9` → `(9, 9)`. Thousands separators (`1,000`) are
+/// accepted. None when the tooltip has no stack line.
+pub fn stack_size(source: &str) -> Option<(u32, u32)> {
+    let line = source
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Stack Size:"))?;
+    let (have, max) = line.trim().split_once('/')?;
+    let parse = |n: &str| n.trim().replace(',', "").parse().ok();
+    Some((parse(have)?, parse(max)?))
+}
+
 impl<'a> ItemMod<'a> {
     fn from_strs(top_line: &'a str, bottom_line: &'a str) -> anyhow::Result<Self> {
         debug!("Parsing {top_line:?}");
@@ -436,6 +536,66 @@ mod test {
         }
     }
 
+    #[test]
+    fn stack_size_reads_full_and_partial_stacks() {
+        let card = "Item Class: Divination Cards\nRarity: Divination Card\nThe Wolf\n--------\nStack Size: 5/5\n";
+        assert_eq!(stack_size(card), Some((5, 5)));
+        assert_eq!(stack_size("Stack Size: 1,234/5,000"), Some((1234, 5000)));
+        assert_eq!(stack_size("Stack Size: 3/9"), Some((3, 9)));
+        assert_eq!(
+            stack_size(include_str!("../tests/example_items/amulet.txt")),
+            None
+        );
+    }
+
+    #[test]
+    fn item_header_fields_are_parsed() {
+        let boots = Item::from_str(include_str!("../tests/example_items/unique.txt")).unwrap();
+        assert_eq!(boots.item_class, "Boots");
+        assert_eq!(boots.base_name, "Nubuck Boots");
+        assert_eq!(boots.rarity(), "Unique");
+        assert_eq!(boots.ilvl, 74);
+        assert_eq!(boots.sockets, "G");
+        assert!(boots.identified);
+        assert!(!boots.corrupted);
+
+        let unid = "Item Class: Rings\nRarity: Rare\nIron Ring\n--------\nItem Level: 68\n--------\nUnidentified\n--------\nCorrupted";
+        let ring = Item::from_str(unid).unwrap();
+        assert_eq!(ring.base_name, "Iron Ring");
+        assert_eq!(ring.rarity(), "Rare");
+        assert!(!ring.identified);
+        assert!(ring.corrupted);
+    }
+
+    #[test]
+    fn magic_name_splits_around_the_base() {
+        let helm = Item::from_str(include_str!("../tests/example_items/magic_helm.txt")).unwrap();
+        assert_eq!(helm.base_name, "Silken Hood");
+        match &helm.item_name {
+            ItemName::Magic { prefix, suffix } => {
+                assert_eq!(
+                    (prefix.as_str(), suffix.as_str()),
+                    ("Phantasm's", "of the Thunderhead")
+                );
+            }
+            other => panic!("expected magic name, got {other:?}"),
+        }
+        let prefix_only =
+            "Item Class: Rings\nRarity: Magic\nGlinting Iron Ring\n--------\nItem Level: 12";
+        let ring = Item::from_str(prefix_only).unwrap();
+        assert_eq!(ring.base_name, "Iron Ring");
+        assert_eq!(ring.item_name.to_string(), "M(p): Glinting");
+    }
+
+    #[test]
+    fn display_name_joins_the_name_block() {
+        assert_eq!(
+            display_name(include_str!("../tests/example_items/magic_helm.txt")),
+            "Phantasm's Silken Hood of the Thunderhead"
+        );
+        assert_eq!(display_name("not a tooltip"), "");
+    }
+
     #[test]
     fn every_example_item_parses() {
         let files = crate::test_support::example_item_files();
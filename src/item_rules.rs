@@ -0,0 +1,118 @@
+//! Rule files for `empty --filter`: decide per item, from its tooltip, whether
+//! the inventory dump moves it or leaves it in place.
+//!
+//! A rule file is JSON, loaded like a chrome file:
+//!
+//! ```json
+//! { "rules": [
+//!     { "rarity": ["Rare"], "class": ["Rings", "Amulets"], "min_ilvl": 60, "unidentified": true },
+//!     { "base": ["Vaal Regalia"], "mods": ["to maximum Energy Shield"] }
+//! ] }
+//! ```
+//!
+//! An item matches the file when ANY rule matches, and a rule matches when
+//! EVERY field it sets matches. List fields match any one entry, compared
+//! case-insensitively; `mods` entries must each appear somewhere in the
+//! tooltip text.
+use serde::{Deserialize, Serialize};
+
+use crate::item::Item;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ItemRules {
+    pub rules: Vec<ItemRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ItemRule {
+    /// `Normal`, `Magic`, `Rare`, `Unique`.
+    #[serde(default)]
+    pub rarity: Vec<String>,
+    /// `Item Class:` value, e.g. `Body Armours`, `Rings`.
+    #[serde(default)]
+    pub class: Vec<String>,
+    /// Base type, e.g. `Iron Ring`.
+    #[serde(default)]
+    pub base: Vec<String>,
+    #[serde(default)]
+    pub min_ilvl: Option<u8>,
+    #[serde(default)]
+    pub max_ilvl: Option<u8>,
+    /// Substrings that must all appear in the tooltip (mod lines).
+    #[serde(default)]
+    pub mods: Vec<String>,
+    /// `true` matches only unidentified items, `false` only identified ones.
+    #[serde(default)]
+    pub unidentified: Option<bool>,
+    #[serde(default)]
+    pub corrupted: Option<bool>,
+}
+
+fn any_eq(wanted: &[String], actual: &str) -> bool {
+    wanted.is_empty() || wanted.iter().any(|w| w.eq_ignore_ascii_case(actual))
+}
+
+impl ItemRule {
+    pub fn matches(&self, item: &Item, text: &str) -> bool {
+        let lower = text.to_lowercase();
+        any_eq(&self.rarity, item.rarity())
+            && any_eq(&self.class, item.item_class)
+            && any_eq(&self.base, item.base_name)
+            && self.min_ilvl.is_none_or(|m| item.ilvl >= m)
+            && self.max_ilvl.is_none_or(|m| item.ilvl <= m)
+            && self.mods.iter().all(|m| lower.contains(&m.to_lowercase()))
+            && self.unidentified.is_none_or(|u| u != item.identified)
+            && self.corrupted.is_none_or(|c| c == item.corrupted)
+    }
+}
+
+impl ItemRules {
+    pub fn matches(&self, item: &Item, text: &str) -> bool {
+        self.rules.iter().any(|r| r.matches(item, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNID_RING: &str = "Item Class: Rings\nRarity: Rare\nIron Ring\n--------\nItem Level: 68\n--------\nUnidentified";
+
+    fn rules(json: &str) -> ItemRules {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn rule_fields_all_have_to_match() {
+        let item = Item::from_str(UNID_RING).unwrap();
+        let r = rules(
+            r#"{"rules": [{"rarity": ["rare"], "class": ["Rings"], "min_ilvl": 60, "max_ilvl": 74, "unidentified": true}]}"#,
+        );
+        assert!(r.matches(&item, UNID_RING));
+        let too_high = rules(r#"{"rules": [{"class": ["Rings"], "min_ilvl": 75}]}"#);
+        assert!(!too_high.matches(&item, UNID_RING));
+        let identified_only = rules(r#"{"rules": [{"unidentified": false}]}"#);
+        assert!(!identified_only.matches(&item, UNID_RING));
+    }
+
+    #[test]
+    fn any_rule_matching_is_enough() {
+        let text = include_str!("../tests/example_items/amulet.txt");
+        let item = Item::from_str(text).unwrap();
+        let r = rules(
+            r#"{"rules": [
+                {"class": ["Boots"]},
+                {"base": ["Onyx Amulet"], "mods": ["cold resistance", "to Dexterity"]}
+            ]}"#,
+        );
+        assert!(r.matches(&item, text));
+        let missing_mod = rules(r#"{"rules": [{"mods": ["to maximum Life"]}]}"#);
+        assert!(!missing_mod.matches(&item, text));
+    }
+
+    #[test]
+    fn empty_file_matches_nothing() {
+        let item = Item::from_str(UNID_RING).unwrap();
+        assert!(!ItemRules::default().matches(&item, UNID_RING));
+    }
+}
@@ -0,0 +1,233 @@
+//! Geometry estimated from the game window size alone.
+//!
+//! PoE scales its UI with the window height: at 1920x1080 the stash panel's
+//! grid is 632 px square at a fixed offset from the window's left edge, the
+//! inventory grid sits at a fixed offset from the right edge, and every cell
+//! is 632 / 12 px. Other sizes multiply those offsets by `height / 1080`;
+//! wider windows only move the right-anchored inventory. [`derive`] turns a
+//! `game_window_region` into the regions, grids and currency points the
+//! calibration commands would capture, for `doctor` to compare against and
+//! for the GUI's auto-calibrate.
+use crate::stash_grid::{CellGrid, MapGrid, NormalGrid, StashGrid};
+use crate::{NamedPoint, ScreenRegion, Settings};
+
+/// Window height the offsets below were measured at.
+const REF_HEIGHT: f64 = 1080.0;
+
+/// Stash grid (x, y, side) from the window's top-left, at 1080p.
+const REF_STASH: (f64, f64, f64) = (16.0, 160.0, 632.0);
+
+/// Inventory grid (offset of its left edge from the window's right edge,
+/// y, width, height) at 1080p.
+const REF_INV: (f64, f64, f64, f64) = (648.0, 588.0, 632.0, 263.0);
+
+/// Map tab's 12x7 map grid (x, y, width, height) from the window's
+/// top-left, at 1080p. It fills the bottom of the stash panel.
+const REF_MAP: (f64, f64, f64, f64) = (16.0, 424.0, 632.0, 368.0);
+
+/// Currency tab slots `calibrate-currency` captures: (name, centre x,
+/// centre y) from the stash grid's top-left, at 1080p.
+const REF_CURRENCY: [(&str, f64, f64); 10] = [
+    ("transmute", 56.0, 40.0),
+    ("alt", 114.0, 40.0),
+    ("annul", 172.0, 40.0),
+    ("chance", 230.0, 40.0),
+    ("exalt", 288.0, 40.0),
+    ("augment", 56.0, 98.0),
+    ("regal", 114.0, 98.0),
+    ("alchemy", 172.0, 98.0),
+    ("chaos", 230.0, 98.0),
+    ("scour", 346.0, 98.0),
+];
+
+/// Side of a currency slot box at 1080p.
+const REF_SLOT: f64 = 46.0;
+
+/// Search-highlight colour for grids with no calibrated colour to keep.
+pub const DEFAULT_HIGHLIGHT: u32 = 0xE7B477FF;
+
+/// Everything [`derive`] estimates. Regions are screen space, grids frame
+/// space (relative to the game window), like their `Settings` fields.
+pub struct Layout {
+    pub inv_region: ScreenRegion,
+    pub stash_region: ScreenRegion,
+    pub map_region: ScreenRegion,
+    pub stash_grid: StashGrid,
+    pub normal_grid: NormalGrid,
+    pub map_grid: MapGrid,
+    pub points: Vec<NamedPoint>,
+}
+
+/// Estimate the layout for a game window at `window`. Grids get
+/// `highlight` as their search-highlight colour.
+pub fn derive(window: ScreenRegion, highlight: u32) -> Layout {
+    let k = window.height as f64 / REF_HEIGHT;
+    let region = |x: f64, y: f64, w: f64, h: f64| ScreenRegion {
+        x: window.x + (x * k).round() as u32,
+        y: window.y + (y * k).round() as u32,
+        width: (w * k).round() as u32,
+        height: (h * k).round() as u32,
+    };
+    let cell = REF_STASH.2 / 12.0 * k;
+
+    let stash_origin = (REF_STASH.0 * k, REF_STASH.1 * k);
+    let map_origin = (REF_MAP.0 * k, REF_MAP.1 * k);
+    let inv_x = (window.width as f64 - REF_INV.0 * k).max(0.0) / k;
+    Layout {
+        inv_region: region(inv_x, REF_INV.1, REF_INV.2, REF_INV.3),
+        stash_region: region(REF_STASH.0, REF_STASH.1, REF_STASH.2, REF_STASH.2),
+        map_region: region(REF_MAP.0, REF_MAP.1, REF_MAP.2, REF_MAP.3),
+        stash_grid: StashGrid::uniform(stash_origin, cell / 2.0, highlight),
+        normal_grid: NormalGrid::uniform(stash_origin, cell, highlight),
+        map_grid: MapGrid::uniform(map_origin, cell, highlight),
+        points: REF_CURRENCY
+            .iter()
+            .map(|&(name, cx, cy)| NamedPoint {
+                name: name.into(),
+                region: region(
+                    REF_STASH.0 + cx - REF_SLOT / 2.0,
+                    REF_STASH.1 + cy - REF_SLOT / 2.0,
+                    REF_SLOT,
+                    REF_SLOT,
+                ),
+            })
+            .collect(),
+    }
+}
+
+impl Layout {
+    /// Fill the regions and grids that are not calibrated yet with the
+    /// estimate; with `replace`, calibrated ones are overwritten too. A
+    /// replaced grid keeps its own highlight colour and spread. Currency
+    /// points are only added under names not already taken, even with
+    /// `replace`: a captured point is never moved.
+    pub fn apply(self, s: &mut Settings, replace: bool) {
+        let fill = |field: &mut Option<ScreenRegion>, estimate| {
+            if replace || field.is_none() {
+                *field = Some(estimate);
+            }
+        };
+        fill(&mut s.inv_region, self.inv_region);
+        fill(&mut s.stash_region, self.stash_region);
+        fill(&mut s.map_region, self.map_region);
+        fill_grid(&mut s.stash_grid, self.stash_grid, replace);
+        fill_grid(&mut s.normal_grid, self.normal_grid, replace);
+        fill_grid(&mut s.map_grid, self.map_grid, replace);
+        let points = s.points.get_or_insert_with(Vec::new);
+        for p in self.points {
+            if !points.iter().any(|old| old.name == p.name) {
+                points.push(p);
+            }
+        }
+    }
+}
+
+fn fill_grid<const C: usize, const R: usize>(
+    field: &mut Option<CellGrid<C, R>>,
+    mut estimate: CellGrid<C, R>,
+    replace: bool,
+) {
+    match field {
+        None => *field = Some(estimate),
+        Some(old) if replace => {
+            estimate.highlight_color = old.highlight_color;
+            estimate.highlight_spread = old.highlight_spread;
+            *old = estimate;
+        }
+        Some(_) => {}
+    }
+}
+
+/// Largest edge distance between two regions, in pixels.
+pub fn deviation(a: ScreenRegion, b: ScreenRegion) -> u32 {
+    let edges = |r: ScreenRegion| [r.x, r.y, r.x + r.width, r.y + r.height];
+    let (a, b) = (edges(a), edges(b));
+    (0..4).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0)
+}
+
+/// How far a calibrated region may sit from the estimate before `doctor`
+/// warns: a fifth of a normal cell.
+pub fn tolerance(window: ScreenRegion) -> u32 {
+    (REF_STASH.2 / 12.0 / 5.0 * window.height as f64 / REF_HEIGHT).ceil() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(x: u32, y: u32, width: u32, height: u32) -> ScreenRegion {
+        ScreenRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn layout_scales_with_height_and_anchors_inventory_right() {
+        let hd = derive(window(0, 0, 1920, 1080), DEFAULT_HIGHLIGHT);
+        assert_eq!((hd.inv_region.x, hd.inv_region.y), (1272, 588));
+        assert_eq!(hd.stash_grid.cell_w, 26);
+        assert_eq!(hd.normal_grid.cell_center(11, 11), (621, 765));
+
+        // 1440p ultrawide at an offset: 4/3 the size, inventory still at
+        // the right edge.
+        let uw = derive(window(100, 40, 3440, 1440), DEFAULT_HIGHLIGHT);
+        assert_eq!(uw.stash_region.x, 100 + 21);
+        assert_eq!(uw.stash_region.width, 843);
+        assert_eq!(uw.inv_region.x + 864, 100 + 3440);
+        assert_eq!(uw.normal_grid.cell_w, 70);
+        let chaos = uw.points.iter().find(|p| p.name == "chaos").unwrap();
+        assert_eq!(chaos.region.center(), (427, 383));
+        assert_eq!(chaos.region.width, 61);
+    }
+
+    #[test]
+    fn apply_fills_gaps_and_keeps_calibration() {
+        let game = window(0, 0, 1920, 1080);
+        let mut s = crate::default_settings();
+        let mut grid = NormalGrid::uniform((10.0, 10.0), 50.0, 0x112233FF);
+        grid.highlight_spread = 9;
+        let corner = grid.cell_center(0, 0);
+        s.normal_grid = Some(grid);
+        s.stash_region = Some(window(20, 150, 600, 600));
+        s.points = Some(vec![NamedPoint {
+            name: "chaos".into(),
+            region: window(1, 2, 3, 4),
+        }]);
+
+        derive(game, DEFAULT_HIGHLIGHT).apply(&mut s, false);
+        assert_eq!(s.stash_region.unwrap().x, 20);
+        assert_eq!(s.normal_grid.as_ref().unwrap().cell_center(0, 0), corner);
+        assert_eq!(s.inv_region.unwrap().x, 1272);
+        assert!(s.map_grid.is_some());
+        let points = s.points.as_ref().unwrap();
+        let chaos: Vec<_> = points.iter().filter(|p| p.name == "chaos").collect();
+        assert_eq!((chaos.len(), chaos[0].region.x), (1, 1));
+        let exalt = points.iter().find(|p| p.name == "exalt").unwrap();
+        assert_eq!(exalt.region.center(), (16 + 288, 160 + 40));
+        assert_eq!(points.len(), 10);
+
+        derive(game, DEFAULT_HIGHLIGHT).apply(&mut s, true);
+        assert_eq!(s.stash_region.unwrap().x, 16);
+        let normal = s.normal_grid.as_ref().unwrap();
+        assert_ne!(normal.cell_center(0, 0), corner);
+        assert_eq!(
+            (normal.highlight_color, normal.highlight_spread),
+            (0x112233FF, 9)
+        );
+        let points = s.points.as_ref().unwrap();
+        assert_eq!(points.len(), 10);
+        let chaos = points.iter().find(|p| p.name == "chaos").unwrap();
+        assert_eq!(chaos.region.x, 1);
+    }
+
+    #[test]
+    fn deviation_is_the_worst_edge() {
+        let a = window(10, 10, 100, 100);
+        assert_eq!(deviation(a, a), 0);
+        assert_eq!(deviation(a, window(12, 9, 100, 104)), 3);
+        assert_eq!(tolerance(window(0, 0, 1920, 1080)), 11);
+    }
+}
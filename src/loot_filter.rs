@@ -0,0 +1,222 @@
+//! Loot-filter section for the chaos/regal recipe: show rare recipe bases for
+//! slots the stash is short on, hide the ones it already has enough of.
+//!
+//! The section lives between two marker comments at the top of an existing
+//! `.filter` file (PoE uses the first block that matches, so it has to come
+//! before the filter's own rare rules). Regenerating replaces only what is
+//! between the markers; the rest of the file is left byte for byte.
+use anyhow::{Context, bail};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::chaos_recipe::{self, ChaosRecipe, RecipeMode, SLOT_NAMES, StashSource};
+
+pub const BEGIN_MARKER: &str =
+    "# ==== little_oil recipe section: BEGIN (regenerated, do not edit) ====";
+pub const END_MARKER: &str = "# ==== little_oil recipe section: END ====";
+
+/// Filter `Class` name per slot, in `SLOT_NAMES` order, up to the weapon
+/// slot (the last one).
+const SLOT_CLASSES: [&str; 7] = [
+    "Body Armours",
+    "Helmets",
+    "Boots",
+    "Gloves",
+    "Belts",
+    "Amulets",
+    "Rings",
+];
+
+/// Filter `Class` names for `slot`. The weapon slot takes the classes the
+/// set planner can use, so the filter never shows a weapon it would skip.
+fn slot_classes(slot: usize) -> Vec<&'static str> {
+    match SLOT_CLASSES.get(slot) {
+        Some(&class) => vec![class],
+        None => chaos_recipe::recipe_weapon_classes(),
+    }
+}
+
+/// Border colour per slot so a glance at the ground says which piece it is.
+const SLOT_COLOURS: [&str; 8] = [
+    "255 0 0",
+    "255 128 0",
+    "255 255 0",
+    "0 255 0",
+    "0 255 255",
+    "0 128 255",
+    "128 0 255",
+    "255 0 255",
+];
+
+/// The marker-wrapped section. `under[slot]` shows that slot's bases,
+/// otherwise they are hidden.
+pub fn recipe_section(under: &[bool; 8], mode: RecipeMode, target: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{BEGIN_MARKER}");
+    let _ = writeln!(out, "# {mode:?} recipe, target {target} sets");
+    for (slot, &show) in under.iter().enumerate() {
+        let classes: Vec<String> = slot_classes(slot)
+            .iter()
+            .map(|c| format!("\"{c}\""))
+            .collect();
+        let _ = writeln!(
+            out,
+            "{} # {}",
+            if show { "Show" } else { "Hide" },
+            SLOT_NAMES[slot]
+        );
+        let _ = writeln!(out, "    Rarity == Rare");
+        let _ = writeln!(out, "    ItemLevel >= {}", mode.min_ilvl());
+        let _ = writeln!(out, "    Class == {}", classes.join(" "));
+        if show {
+            let _ = writeln!(out, "    SetBorderColor {} 255", SLOT_COLOURS[slot]);
+            let _ = writeln!(out, "    SetFontSize 40");
+        } else {
+            let _ = writeln!(out, "    SetFontSize 18");
+        }
+        out.push('\n');
+    }
+    let _ = writeln!(out, "{END_MARKER}");
+    out
+}
+
+/// Put `section` into `filter`: replace an existing marked section, or
+/// insert it at the top. Splicing the same section twice is a no-op.
+pub fn splice(filter: &str, section: &str) -> anyhow::Result<String> {
+    match (filter.find(BEGIN_MARKER), filter.find(END_MARKER)) {
+        (Some(begin), Some(end)) if begin < end => {
+            let mut after = end + END_MARKER.len();
+            if filter[after..].starts_with("\r\n") {
+                after += 2;
+            } else if filter[after..].starts_with('\n') {
+                after += 1;
+            }
+            Ok(format!("{}{section}{}", &filter[..begin], &filter[after..]))
+        }
+        (None, None) => Ok(format!("{section}\n{filter}")),
+        _ => bail!("recipe section markers are unbalanced — remove the little_oil section by hand"),
+    }
+}
+
+/// Tally the recipe tabs and rewrite `path`'s recipe section. Returns
+/// whether the file changed.
+fn regenerate(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    source: &StashSource,
+    path: &Path,
+    target: usize,
+    mode: RecipeMode,
+) -> anyhow::Result<bool> {
+    let under = chaos_recipe::slots_under_target(app, cr_config, source, mode, target)?;
+    let old = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read filter {}", path.display()))?;
+    let new = splice(&old, &recipe_section(&under, mode, target))?;
+    if new == old {
+        return Ok(false);
+    }
+    std::fs::write(path, new).with_context(|| format!("failed to write {}", path.display()))?;
+    let shown: Vec<&str> = (0..8)
+        .filter(|&s| under[s])
+        .map(|s| SLOT_NAMES[s])
+        .collect();
+    println!(
+        "Filter updated; showing: {}",
+        if shown.is_empty() {
+            "nothing".into()
+        } else {
+            shown.join(", ")
+        }
+    );
+    Ok(true)
+}
+
+/// `filter <file> <sets>`: write the section once, or every `watch` seconds
+/// until the process is stopped.
+pub fn run(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    source: &StashSource,
+    path: &Path,
+    target: usize,
+    mode: RecipeMode,
+    watch: Option<u64>,
+) -> anyhow::Result<()> {
+    let changed = regenerate(app, cr_config, source, path, target, mode)?;
+    if !changed {
+        println!("Filter already up to date");
+    }
+    let Some(secs) = watch else {
+        println!("Reload the filter in game (Options → Game → Item Filter) to apply it");
+        return Ok(());
+    };
+    println!("Watching: regenerating every {secs}s (Ctrl+C to stop)");
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(secs));
+        match regenerate(app, cr_config, source, path, target, mode) {
+            Ok(true) => println!("Reload the filter in game to apply it"),
+            Ok(false) => {}
+            Err(e) => println!("Filter refresh failed: {e:#}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_FILTER: &str = "Show\n    Rarity == Unique\n    SetFontSize 45\n";
+
+    #[test]
+    fn section_shows_only_slots_under_target() {
+        let mut under = [false; 8];
+        under[3] = true;
+        let s = recipe_section(&under, RecipeMode::Chaos, 4);
+        assert!(s.starts_with(BEGIN_MARKER));
+        assert!(s.trim_end().ends_with(END_MARKER));
+        assert!(s.contains(
+            "Show # Gloves\n    Rarity == Rare\n    ItemLevel >= 60\n    Class == \"Gloves\""
+        ));
+        assert!(s.contains("Hide # Boots"));
+        assert_eq!(s.matches("Show").count(), 1);
+        assert!(recipe_section(&under, RecipeMode::Regal, 4).contains("ItemLevel >= 75"));
+    }
+
+    #[test]
+    fn weapon_rule_lists_only_classes_the_planner_uses() {
+        let weapons = slot_classes(7);
+        for class in ["Bows", "Daggers", "One Hand Swords", "Staves", "Wands"] {
+            assert!(weapons.contains(&class), "{class}");
+        }
+        // 2x3 and 2x2 one-handers and 1x4 thrusting swords never fit a set.
+        for class in [
+            "Claws",
+            "One Hand Axes",
+            "One Hand Maces",
+            "Sceptres",
+            "Thrusting One Hand Swords",
+        ] {
+            assert!(!weapons.contains(&class), "{class}");
+        }
+    }
+
+    #[test]
+    fn splice_is_idempotent_and_keeps_user_rules() {
+        let a = recipe_section(&[true; 8], RecipeMode::Chaos, 2);
+        let b = recipe_section(&[false; 8], RecipeMode::Chaos, 2);
+        let once = splice(USER_FILTER, &a).unwrap();
+        assert!(once.starts_with(BEGIN_MARKER));
+        assert!(once.ends_with(USER_FILTER));
+        assert_eq!(splice(&once, &a).unwrap(), once);
+
+        let replaced = splice(&once, &b).unwrap();
+        assert_eq!(replaced, splice(USER_FILTER, &b).unwrap());
+        assert_eq!(replaced.matches(BEGIN_MARKER).count(), 1);
+    }
+
+    #[test]
+    fn splice_refuses_a_half_removed_section() {
+        let broken = format!("{BEGIN_MARKER}\n{USER_FILTER}");
+        assert!(splice(&broken, "x").is_err());
+    }
+}
@@ -13,16 +13,28 @@ use screenshot::ScreenshotData;
 
 mod app;
 mod auto_roll;
+mod base_types;
+mod calibration;
 mod chaos_recipe;
-mod dicts;
+mod colour;
 mod gui;
 mod health;
+mod inventory;
 pub mod item;
+mod item_rules;
+mod layout;
+mod loot_filter;
 mod platform;
+mod pricing;
+mod profiles;
 mod screenshot;
+mod stash_api;
+mod stash_cache;
 mod stash_grid;
+mod stash_history;
 #[cfg(test)]
 mod test_support;
+mod vendor_recipes;
 pub use app::App;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +44,11 @@ pub struct Settings {
     pull_delay: u64,
     #[serde(default = "default_div_delay")]
     div_delay: u64,
+    /// Card slots on one page of the Divination Card tab, [cols, rows], laid
+    /// out evenly inside the `div-tab` point's box. Set with calibrate-div,
+    /// which slurps that box too.
+    #[serde(default)]
+    div_tab_slots: Option<[u32; 2]>,
     /// Inter-click settle for the roll macro (auto_roll). Tune down on a fast
     /// machine; raise if orbs don't get picked up. Set via config.json.
     #[serde(default = "default_roll_click_delay")]
@@ -51,6 +68,36 @@ pub struct Settings {
     /// (index = col * 5 + row) to match the existing loop order.
     #[serde(default)]
     inv_samples: Option<Vec<[u32; 3]>>,
+    /// How far each `inv_samples` probe strayed across the calibration reads
+    /// (see `colour::stats`), same layout. Added to `colour_tolerance`.
+    #[serde(default)]
+    inv_sample_spread: Option<Vec<[u8; 3]>>,
+    /// Three probe colors per empty quad tab cell, 576 cells, column-major
+    /// (index = col * 24 + row). Sampled with `little_oil reset_stash` on an
+    /// empty quad tab; lets `stash copy --all` find items without a search.
+    #[serde(default)]
+    stash_samples: Option<Vec<[u32; 3]>>,
+    /// Spread of each `stash_samples` probe, like `inv_sample_spread`.
+    #[serde(default)]
+    stash_sample_spread: Option<Vec<[u8; 3]>>,
+    /// Three probe colors per empty map tab cell, 84 cells, column-major
+    /// (index = col * 7 + row). Sampled with `little_oil reset_map`; lets
+    /// `load-map` take the first map without a search.
+    #[serde(default)]
+    map_samples: Option<Vec<[u32; 3]>>,
+    /// Spread of each `map_samples` probe, like `inv_sample_spread`.
+    #[serde(default)]
+    map_sample_spread: Option<Vec<[u8; 3]>>,
+    /// Max per-channel colour difference still read as the calibrated colour
+    /// (empty slots, search highlights). Raise when detection misses under
+    /// HDR or a brightness change. Set via config.json or the GUI.
+    #[serde(default = "default_colour_tolerance")]
+    colour_tolerance: u8,
+    /// Inventory cells the macros must never touch (portal/wisdom stacks,
+    /// kept items), same column-major layout as `inv_samples`. Toggle with
+    /// `little_oil lock <col> <row>` or by clicking the GUI Inventory overlay.
+    #[serde(default)]
+    inv_locked: Option<Vec<bool>>,
     #[serde(default)]
     pub platform: Option<Platform>,
     #[serde(default)]
@@ -59,6 +106,16 @@ pub struct Settings {
     pub stash_region: Option<ScreenRegion>,
     #[serde(default)]
     pub game_window_region: Option<ScreenRegion>,
+    /// Game window bounds as the platform reported them when the regions
+    /// were last anchored. On disk every other region and point is stored as
+    /// an offset from its top-left corner; macros re-anchor to wherever the
+    /// window is now (see [`Settings::reanchor`]).
+    #[serde(default)]
+    pub window_anchor: Option<ScreenRegion>,
+    /// Set on disk when the regions are offsets from `window_anchor`. Always
+    /// false in memory. Old configs load as false (absolute) and migrate.
+    #[serde(default)]
+    window_relative: bool,
     /// Relative device units emitted per screen pixel of pointer motion.
     /// Depends on pointer DPI, compositor sensitivity, and accel profile, so it
     /// is machine-specific. Measure with: little_oil calibrate-pointer
@@ -66,6 +123,9 @@ pub struct Settings {
     pub pointer_scale: Option<f32>,
     #[serde(default)]
     pub stash_grid: Option<stash_grid::StashGrid>,
+    /// 12x12 grid for normal/premium tabs. Measure with: little_oil calibrate-normal
+    #[serde(default)]
+    pub normal_grid: Option<stash_grid::NormalGrid>,
     #[serde(default)]
     pub map_region: Option<ScreenRegion>,
     #[serde(default)]
@@ -73,6 +133,17 @@ pub struct Settings {
     /// Named clickable points (currency slots, filter button, …), screen space.
     #[serde(default)]
     pub points: Option<Vec<NamedPoint>>,
+    /// Named points `load-map` ctrl-clicks into the map device after the map
+    /// (scarabs, fragments), in order. Calibrate each with calibrate-point.
+    #[serde(default)]
+    map_device_points: Vec<String>,
+    /// Named calibration sets (see `profiles`), by name.
+    #[serde(default)]
+    profiles: std::collections::BTreeMap<String, profiles::Profile>,
+    /// Profile the live calibration belongs to; `profile use` saves back
+    /// into it before switching.
+    #[serde(default)]
+    active_profile: Option<String>,
     /// True once the GUI first-run wizard has been completed. Cosmetic only —
     /// decides which tab the GUI opens on. Old configs load as false via serde.
     #[serde(default)]
@@ -84,6 +155,203 @@ impl Settings {
         let platform = self.platform.unwrap_or_else(Platform::detect);
         platform.screenshot(self)
     }
+
+    /// The inventory lock mask, empty when nothing was ever locked. Index it
+    /// like `inv_samples` (col * 5 + row); missing entries are unlocked.
+    pub(crate) fn inv_locked(&self) -> &[bool] {
+        self.inv_locked.as_deref().unwrap_or(&[])
+    }
+
+    pub(crate) fn colour_matcher(&self) -> colour::Matcher {
+        colour::Matcher {
+            tolerance: self.colour_tolerance,
+        }
+    }
+
+    /// The calibrated empty inventory slot colours.
+    pub(crate) fn inv_empty_samples(&self) -> anyhow::Result<colour::ProbeSamples> {
+        match &self.inv_samples {
+            Some(s) if s.len() == 60 => Ok(colour::ProbeSamples {
+                colours: s.clone(),
+                spread: self.inv_sample_spread.clone().unwrap_or_default(),
+                matcher: self.colour_matcher(),
+            }),
+            _ => bail!("Inventory colors not calibrated — run: little_oil reset_inv"),
+        }
+    }
+
+    /// The calibrated empty quad tab cell colours.
+    pub(crate) fn stash_empty_samples(&self) -> anyhow::Result<colour::ProbeSamples> {
+        match &self.stash_samples {
+            Some(s) if s.len() == stash_grid::QUAD_COLS * stash_grid::QUAD_ROWS => {
+                Ok(colour::ProbeSamples {
+                    colours: s.clone(),
+                    spread: self.stash_sample_spread.clone().unwrap_or_default(),
+                    matcher: self.colour_matcher(),
+                })
+            }
+            _ => bail!("Quad tab colors not calibrated — run: little_oil reset_stash"),
+        }
+    }
+
+    /// The calibrated empty map tab cell colours.
+    pub(crate) fn map_empty_samples(&self) -> anyhow::Result<colour::ProbeSamples> {
+        match &self.map_samples {
+            Some(s) if s.len() == stash_grid::MAP_COLS * stash_grid::MAP_ROWS => {
+                Ok(colour::ProbeSamples {
+                    colours: s.clone(),
+                    spread: self.map_sample_spread.clone().unwrap_or_default(),
+                    matcher: self.colour_matcher(),
+                })
+            }
+            _ => bail!("Map tab colors not calibrated — run: little_oil reset_map"),
+        }
+    }
+
+    /// The calibrated grid for a quad (`quad`) or normal stash tab.
+    pub(crate) fn tab_grid(&self, quad: bool) -> anyhow::Result<stash_grid::TabGrid> {
+        use stash_grid::TabGrid;
+        match (quad, &self.stash_grid, &self.normal_grid) {
+            (true, Some(g), _) => Ok(TabGrid::Quad(g.clone())),
+            (false, _, Some(g)) => Ok(TabGrid::Normal(g.clone())),
+            (true, None, _) => bail!("Stash grid not calibrated — run: little_oil calibrate-stash"),
+            (false, _, None) => {
+                bail!("Normal tab grid not calibrated — run: little_oil calibrate-normal")
+            }
+        }
+    }
+
+    /// Every calibrated screen region that lives inside the game window:
+    /// the window region itself, inventory, stash, map and named points.
+    fn window_regions(&mut self) -> impl Iterator<Item = &mut ScreenRegion> {
+        self.game_window_region
+            .iter_mut()
+            .chain(self.inv_region.iter_mut())
+            .chain(self.stash_region.iter_mut())
+            .chain(self.map_region.iter_mut())
+            .chain(
+                self.points
+                    .iter_mut()
+                    .flatten()
+                    .map(|p: &mut NamedPoint| &mut p.region),
+            )
+    }
+
+    /// Move every window region by (dx, dy) screen pixels, clamping at 0.
+    fn shift_regions(&mut self, dx: i64, dy: i64) {
+        for r in self.window_regions() {
+            r.x = (r.x as i64 + dx).max(0) as u32;
+            r.y = (r.y as i64 + dy).max(0) as u32;
+        }
+    }
+
+    /// [`Settings::window_regions`] by the name `set-region` and
+    /// `calibrate-point` know them by.
+    fn named_window_regions(&self) -> Vec<(String, ScreenRegion)> {
+        [
+            ("window", self.game_window_region),
+            ("inventory", self.inv_region),
+            ("stash", self.stash_region),
+            ("map", self.map_region),
+        ]
+        .into_iter()
+        .filter_map(|(name, r)| Some((name.to_string(), r?)))
+        .chain(
+            self.points
+                .iter()
+                .flatten()
+                .map(|p| (format!("point '{}'", p.name), p.region)),
+        )
+        .collect()
+    }
+
+    /// The on-disk form: regions as offsets from the window anchor. Without
+    /// an anchor (the platform cannot find the window) they stay absolute.
+    /// Offsets are unsigned, so a region that starts left of or above the
+    /// window is reported instead of being clamped onto its edge.
+    pub(crate) fn to_disk(&self) -> anyhow::Result<Settings> {
+        let mut disk = self.clone();
+        if let Some(anchor) = self.window_anchor {
+            let outside: Vec<String> = self
+                .named_window_regions()
+                .into_iter()
+                .filter(|(_, r)| r.x < anchor.x || r.y < anchor.y)
+                .map(|(name, _)| name)
+                .collect();
+            if !outside.is_empty() {
+                bail!(
+                    "{} start(s) outside the game window at ({}, {}) — re-run set-region (or calibrate-point) for them, or set-region window if the window moved",
+                    outside.join(", "),
+                    anchor.x,
+                    anchor.y
+                );
+            }
+            disk.shift_regions(-(anchor.x as i64), -(anchor.y as i64));
+            disk.window_relative = true;
+        }
+        Ok(disk)
+    }
+
+    /// Back from [`Settings::to_disk`]: absolute screen regions, placed at
+    /// the anchor the config was saved with.
+    fn into_memory(mut self) -> Settings {
+        if self.window_relative
+            && let Some(anchor) = self.window_anchor
+        {
+            self.shift_regions(anchor.x as i64, anchor.y as i64);
+        }
+        self.window_relative = false;
+        self
+    }
+
+    /// Follow the game window to `found` (its current bounds): shift every
+    /// region by how far the window moved. Fails when the window size
+    /// changed, since the grids and slot offsets no longer line up. Without a
+    /// saved anchor, `found` becomes the anchor. Returns whether anything
+    /// moved.
+    pub(crate) fn reanchor(&mut self, found: ScreenRegion) -> anyhow::Result<bool> {
+        let Some(anchor) = self.window_anchor else {
+            self.window_anchor = Some(found);
+            return Ok(false);
+        };
+        if anchor.width.abs_diff(found.width) > WINDOW_SIZE_SLACK
+            || anchor.height.abs_diff(found.height) > WINDOW_SIZE_SLACK
+        {
+            bail!(
+                "Game window is {}x{} but was calibrated at {}x{} — resize it back, or re-run: little_oil set-region window (and the regions/grids that depend on it)",
+                found.width,
+                found.height,
+                anchor.width,
+                anchor.height
+            );
+        }
+        if (anchor.x, anchor.y) == (found.x, found.y) {
+            return Ok(false);
+        }
+        self.shift_regions(
+            found.x as i64 - anchor.x as i64,
+            found.y as i64 - anchor.y as i64,
+        );
+        self.window_anchor = Some(found);
+        Ok(true)
+    }
+
+    /// Save `region` as the game window region, anchored to the window's
+    /// current bounds so later moves are followed from here.
+    pub(crate) fn set_game_window(&mut self, region: ScreenRegion) {
+        self.game_window_region = Some(region);
+        let platform = self.platform.unwrap_or_else(Platform::detect);
+        self.window_anchor = platform.find_game_window();
+    }
+
+    /// Flip the lock on inventory cell (col, row). Returns the new state.
+    pub(crate) fn toggle_inv_lock(&mut self, col: u32, row: u32) -> bool {
+        let mask = self.inv_locked.get_or_insert_with(|| vec![false; 60]);
+        mask.resize(60, false);
+        let cell = &mut mask[(col * 5 + row) as usize];
+        *cell = !*cell;
+        *cell
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -107,6 +375,10 @@ pub struct NamedPoint {
     pub region: ScreenRegion,
 }
 
+/// Pixels the game window may grow or shrink (borders, rounding) before
+/// its calibrations are considered stale.
+pub(crate) const WINDOW_SIZE_SLACK: u32 = 2;
+
 const fn default_pull_delay() -> u64 {
     50
 }
@@ -119,6 +391,9 @@ const fn default_roll_click_delay() -> u64 {
 const fn default_roll_read_delay() -> u64 {
     75
 }
+const fn default_colour_tolerance() -> u8 {
+    colour::DEFAULT_TOLERANCE
+}
 const fn default_focus_clicks() -> u32 {
     2
 }
@@ -128,19 +403,33 @@ fn default_settings() -> Settings {
         chaos_recipe_settings: None,
         pull_delay: 50,
         div_delay: 100,
+        div_tab_slots: None,
         roll_click_delay: 10,
         roll_read_delay: 75,
         focus_clicks: 2,
         inv_samples: None,
+        inv_sample_spread: None,
+        stash_samples: None,
+        stash_sample_spread: None,
+        map_samples: None,
+        map_sample_spread: None,
+        colour_tolerance: colour::DEFAULT_TOLERANCE,
+        inv_locked: None,
         platform: None,
         inv_region: None,
         stash_region: None,
         game_window_region: None,
+        window_anchor: None,
+        window_relative: false,
         pointer_scale: Some(1.25),
         stash_grid: None,
+        normal_grid: None,
         map_region: None,
         map_grid: None,
         points: None,
+        map_device_points: Vec::new(),
+        profiles: Default::default(),
+        active_profile: None,
         setup_complete: false,
     }
 }
@@ -159,6 +448,29 @@ pub fn rolls_log_path() -> anyhow::Result<PathBuf> {
         .map(|d| d.join("little_oil").join("rolls.log"))
 }
 
+/// Saved stash API responses: $XDG_CONFIG_HOME/little_oil/stash-cache/
+pub fn stash_cache_dir() -> anyhow::Result<PathBuf> {
+    dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("no XDG config directory — set XDG_CONFIG_HOME or HOME"))
+        .map(|d| d.join("little_oil").join("stash-cache"))
+}
+
+/// Compressed stash snapshots for `snapshot`/`diff`:
+/// $XDG_CONFIG_HOME/little_oil/snapshots/
+pub fn snapshots_dir() -> anyhow::Result<PathBuf> {
+    dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("no XDG config directory — set XDG_CONFIG_HOME or HOME"))
+        .map(|d| d.join("little_oil").join("snapshots"))
+}
+
+/// Downloaded poe.ninja overview exports for `value`:
+/// $XDG_CONFIG_HOME/little_oil/prices/
+pub fn prices_dir() -> anyhow::Result<PathBuf> {
+    dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("no XDG config directory — set XDG_CONFIG_HOME or HOME"))
+        .map(|d| d.join("little_oil").join("prices"))
+}
+
 pub fn save_config<T: Serialize>(path: &Path, set: &T) -> Result<(), std::io::Error> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
@@ -202,13 +514,112 @@ fn main() -> anyhow::Result<()> {
     #[cfg(target_os = "windows")]
     crate::platform::windows::set_dpi_awareness();
     tracing_subscriber::fmt::init();
-    let mut set = load_config(&config_path()?, Some(&default_settings()))?;
+    let set: Settings = load_config(&config_path()?, Some(&default_settings()))?;
+    let mut set = set.into_memory();
+    let mut dirty = false;
     // Ensure platform is set (auto-detect on first run, or use config value).
     if set.platform.is_none() {
         set.platform = Some(Platform::detect());
-        save_config(&config_path()?, &set)?;
+        dirty = true;
+    }
+    // Migrate absolute configs: anchor them to the window as it is now, so
+    // the next save stores window-relative offsets.
+    if set.window_anchor.is_none()
+        && set.game_window_region.is_some()
+        && let Some(found) = set
+            .platform
+            .unwrap_or_else(Platform::detect)
+            .find_game_window()
+    {
+        set.window_anchor = Some(found);
+        match set.to_disk() {
+            Ok(_) => {
+                println!(
+                    "Anchored calibration to the game window; regions are now stored window-relative"
+                );
+                dirty = true;
+            }
+            Err(e) => {
+                tracing::warn!("keeping absolute regions: {e:#}");
+                set.window_anchor = None;
+            }
+        }
+    }
+    if dirty {
+        save_config(&config_path()?, &set.to_disk()?)?;
     }
     let app = App::new(set)?;
     let args: Vec<String> = std::env::args().skip(1).collect();
     app.run(&args)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> ScreenRegion {
+        ScreenRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn calibrated() -> Settings {
+        let mut s = default_settings();
+        s.window_anchor = Some(region(100, 50, 1920, 1080));
+        s.game_window_region = Some(region(100, 50, 1920, 1080));
+        s.stash_region = Some(region(117, 210, 632, 632));
+        s.points = Some(vec![NamedPoint {
+            name: "chaos".into(),
+            region: region(300, 150, 40, 40),
+        }]);
+        s
+    }
+
+    #[test]
+    fn regions_are_stored_relative_to_the_window() {
+        let disk = calibrated().to_disk().unwrap();
+        assert!(disk.window_relative);
+        assert_eq!(disk.stash_region.unwrap().x, 17);
+        assert_eq!(disk.points.as_ref().unwrap()[0].region.y, 100);
+
+        let json = serde_json::to_string(&disk).unwrap();
+        let back = serde_json::from_str::<Settings>(&json)
+            .unwrap()
+            .into_memory();
+        assert!(!back.window_relative);
+        assert_eq!(back.stash_region.unwrap().y, 210);
+
+        // Old absolute configs have no flag and load unchanged.
+        let mut old = serde_json::to_value(calibrated()).unwrap();
+        old.as_object_mut().unwrap().remove("window_relative");
+        let old = serde_json::from_value::<Settings>(old)
+            .unwrap()
+            .into_memory();
+        assert_eq!(old.stash_region.unwrap().x, 117);
+    }
+
+    #[test]
+    fn regions_outside_the_window_are_reported_not_clamped() {
+        let mut s = calibrated();
+        s.points.as_mut().unwrap().push(NamedPoint {
+            name: "edge".into(),
+            region: region(90, 60, 20, 20),
+        });
+        let err = s.to_disk().unwrap_err().to_string();
+        assert!(err.starts_with("point 'edge' start(s) outside"), "{err}");
+    }
+
+    #[test]
+    fn reanchor_follows_moves_and_rejects_resizes() {
+        let mut s = calibrated();
+        assert!(!s.reanchor(region(100, 50, 1920, 1080)).unwrap());
+        assert!(s.reanchor(region(0, 1130, 1921, 1080)).unwrap());
+        assert_eq!(s.stash_region.unwrap().x, 17);
+        assert_eq!(s.points.as_ref().unwrap()[0].region.y, 1230);
+        assert_eq!(s.game_window_region.unwrap().y, 1130);
+        assert!(s.reanchor(region(0, 1130, 1280, 720)).is_err());
+    }
+}
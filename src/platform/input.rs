@@ -18,6 +18,7 @@ pub(crate) enum InputButton {
 pub(crate) enum InputKey {
     Ctrl,
     Alt,
+    Shift,
     C,
 }
 
@@ -191,7 +192,7 @@ impl Input {
 
     // ── keyboard ────────────────────────────────────────────────
 
-    /// Press or release a keyboard key (minimal set: Ctrl, Alt, C).
+    /// Press or release a keyboard key (minimal set: Ctrl, Alt, Shift, C).
     pub(crate) fn key(&mut self, key: InputKey, pressed: bool) {
         #[cfg(target_os = "linux")]
         {
@@ -199,6 +200,7 @@ impl Input {
             let code = match key {
                 InputKey::Ctrl => key_codes::KEY_LEFTCTRL,
                 InputKey::Alt => key_codes::KEY_LEFTALT,
+                InputKey::Shift => key_codes::KEY_LEFTSHIFT,
                 InputKey::C => key_codes::KEY_C,
             };
             let Input::Linux { device, .. } = self;
@@ -214,13 +216,15 @@ impl Input {
         unsafe {
             use windows::Win32::UI::Input::KeyboardAndMouse::{
                 INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_KEYUP,
-                SendInput, VK_C, VK_CONTROL, VK_MENU,
+                SendInput, VK_C, VK_CONTROL, VK_MENU, VK_SHIFT,
             };
             let (vk, flags) = match (key, pressed) {
                 (InputKey::Ctrl, true) => (VK_CONTROL, KEYBD_EVENT_FLAGS(0)),
                 (InputKey::Ctrl, false) => (VK_CONTROL, KEYEVENTF_KEYUP),
                 (InputKey::Alt, true) => (VK_MENU, KEYBD_EVENT_FLAGS(0)),
                 (InputKey::Alt, false) => (VK_MENU, KEYEVENTF_KEYUP),
+                (InputKey::Shift, true) => (VK_SHIFT, KEYBD_EVENT_FLAGS(0)),
+                (InputKey::Shift, false) => (VK_SHIFT, KEYEVENTF_KEYUP),
                 (InputKey::C, true) => (VK_C, KEYBD_EVENT_FLAGS(0)),
                 (InputKey::C, false) => (VK_C, KEYEVENTF_KEYUP),
             };
@@ -0,0 +1,631 @@
+//! Stash valuation against a local price table in the poe.ninja export shape
+//! (`{"lines": [...]}` per overview; currency overviews use
+//! `currencyTypeName`/`chaosEquivalent`, item overviews `name`/`chaosValue`).
+//!
+//! Items come from the stash API (`value <tab>`) or from a saved `stash copy`
+//! dump (`value --file`), and are reduced to a [`Valued`] — just the fields
+//! prices depend on: name, stack size, links, gem level/quality, map tier.
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::chaos_recipe::{self, ChaosRecipe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Currency,
+    Fragment,
+    DivinationCard,
+    Gem,
+    Map,
+    Unique,
+    Other,
+}
+
+/// One stash item as far as pricing is concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Valued {
+    pub category: Category,
+    /// Unique name, or the type line for everything else.
+    pub name: String,
+    pub base: String,
+    /// Magic rarity: a tooltip's one name line is the base with its affixes.
+    pub magic: bool,
+    pub stack: u32,
+    /// Largest linked socket group.
+    pub links: u8,
+    pub gem_level: u8,
+    pub gem_quality: u8,
+    pub map_tier: u8,
+}
+
+/// The stash API item fields pricing (and `vendor_recipes`) reads.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiItem {
+    #[serde(default)]
+    pub(crate) x: usize,
+    #[serde(default)]
+    pub(crate) y: usize,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    pub(crate) type_line: String,
+    #[serde(default)]
+    base_type: String,
+    #[serde(default)]
+    pub(crate) frame_type: u8,
+    #[serde(default)]
+    stack_size: Option<u32>,
+    #[serde(default)]
+    max_stack_size: Option<u32>,
+    #[serde(default)]
+    pub(crate) sockets: Vec<ApiSocket>,
+    #[serde(default)]
+    properties: Vec<ApiProperty>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct ApiSocket {
+    pub(crate) group: u8,
+    /// `R`, `G`, `B`, `W`, `A` (abyss), `DV` (delve).
+    #[serde(default, rename = "sColour")]
+    pub(crate) colour: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ApiProperty {
+    name: String,
+    #[serde(default)]
+    values: Vec<(String, serde_json::Value)>,
+}
+
+impl ApiItem {
+    /// Leading number of the first value of property `name`, 0 if absent.
+    pub(crate) fn property(&self, name: &str) -> u8 {
+        self.properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.values.first())
+            .map_or(0, |(v, _)| leading_number(v))
+    }
+}
+
+/// Leading integer of `"20 (Max)"`, `"+23%"`, `"16"`.
+fn leading_number(s: &str) -> u8 {
+    let digits: String = s
+        .trim_start_matches('+')
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().unwrap_or(0)
+}
+
+/// Legacy responses prefix names with `<<set:MS>><<set:M>><<set:S>>`.
+fn strip_markup(s: &str) -> &str {
+    s.rsplit_once(">>").map_or(s, |(_, name)| name)
+}
+
+impl From<&ApiItem> for Valued {
+    fn from(item: &ApiItem) -> Valued {
+        let prop = |name: &str| item.property(name);
+        let map_tier = prop("Map Tier");
+        let category = match item.frame_type {
+            3 => Category::Unique,
+            4 => Category::Gem,
+            5 => Category::Currency,
+            6 => Category::DivinationCard,
+            _ if map_tier > 0 => Category::Map,
+            0 if item.max_stack_size.is_some() => Category::Fragment,
+            _ => Category::Other,
+        };
+        let mut groups: HashMap<u8, u8> = HashMap::new();
+        for s in &item.sockets {
+            *groups.entry(s.group).or_default() += 1;
+        }
+        let name = match category {
+            Category::Unique => strip_markup(&item.name),
+            _ => strip_markup(&item.type_line),
+        };
+        Valued {
+            category,
+            name: name.to_string(),
+            base: item.base_type.clone(),
+            magic: item.frame_type == 1,
+            stack: item.stack_size.unwrap_or(1),
+            links: groups.values().copied().max().unwrap_or(0),
+            gem_level: prop("Level"),
+            gem_quality: prop("Quality"),
+            map_tier,
+        }
+    }
+}
+
+/// One Ctrl+C tooltip. Gem level and quality are the first `Level:` /
+/// `Quality:` lines, which sit above the `Requirements:` block.
+pub fn from_tooltip(text: &str) -> Option<Valued> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let class = lines.iter().find_map(|l| l.strip_prefix("Item Class: "))?;
+    let rarity = lines.iter().find_map(|l| l.strip_prefix("Rarity: "))?;
+    let names: Vec<&str> = lines
+        .iter()
+        .skip_while(|l| !l.starts_with("Rarity:"))
+        .skip(1)
+        .take_while(|l| **l != "--------")
+        .copied()
+        .collect();
+    let before_reqs = || lines.iter().take_while(|l| **l != "Requirements:");
+    let number = |prefix: &str| {
+        before_reqs()
+            .find_map(|l| l.strip_prefix(prefix))
+            .map_or(0, leading_number)
+    };
+    let links = lines
+        .iter()
+        .find_map(|l| l.strip_prefix("Sockets: "))
+        .map_or(0, |s| {
+            s.split_whitespace()
+                .map(|g| g.split('-').count() as u8)
+                .max()
+                .unwrap_or(0)
+        });
+    let category = match (rarity, class) {
+        ("Unique", _) => Category::Unique,
+        (_, "Divination Cards") => Category::DivinationCard,
+        (_, "Maps") => Category::Map,
+        (_, c) if c.ends_with("Gems") => Category::Gem,
+        (_, "Map Fragments" | "Misc Map Items") => Category::Fragment,
+        (_, c) if c.contains("Currency") => Category::Currency,
+        _ => Category::Other,
+    };
+    Some(Valued {
+        category,
+        name: names.first()?.to_string(),
+        base: names.last()?.to_string(),
+        magic: rarity == "Magic",
+        stack: crate::item::stack_size(text).map_or(1, |(have, _)| have),
+        links,
+        gem_level: number("Level: "),
+        gem_quality: number("Quality: "),
+        map_tier: number("Map Tier: "),
+    })
+}
+
+/// A `stash copy` dump: tooltips separated by `--------`, each starting at
+/// its `Item Class:` line (the separator also appears inside tooltips).
+pub fn parse_copy(text: &str) -> Vec<Valued> {
+    let mut tooltips: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("Item Class:") {
+            tooltips.push(String::new());
+        }
+        if let Some(t) = tooltips.last_mut() {
+            t.push_str(line);
+            t.push('\n');
+        }
+    }
+    tooltips.iter().filter_map(|t| from_tooltip(t)).collect()
+}
+
+/// One row of a poe.ninja overview.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceLine {
+    #[serde(alias = "currencyTypeName")]
+    pub name: String,
+    #[serde(default)]
+    pub base_type: Option<String>,
+    #[serde(default)]
+    pub variant: Option<String>,
+    #[serde(default)]
+    pub links: Option<u8>,
+    #[serde(default)]
+    pub gem_level: Option<u8>,
+    #[serde(default)]
+    pub gem_quality: Option<u8>,
+    #[serde(default)]
+    pub map_tier: Option<u8>,
+    #[serde(default)]
+    pub corrupted: bool,
+    #[serde(alias = "chaosEquivalent")]
+    pub chaos_value: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PriceFile {
+    Overview { lines: Vec<PriceLine> },
+    Lines(Vec<PriceLine>),
+}
+
+/// Price lines by name. Chaos Orb is always worth 1.
+pub struct PriceTable {
+    by_name: HashMap<String, Vec<PriceLine>>,
+}
+
+impl PriceTable {
+    pub fn from_lines(lines: impl IntoIterator<Item = PriceLine>) -> PriceTable {
+        let mut by_name: HashMap<String, Vec<PriceLine>> = HashMap::new();
+        for line in lines {
+            by_name.entry(line.name.clone()).or_default().push(line);
+        }
+        by_name.entry("Chaos Orb".into()).or_insert_with(|| {
+            vec![PriceLine {
+                name: "Chaos Orb".into(),
+                base_type: None,
+                variant: None,
+                links: None,
+                gem_level: None,
+                gem_quality: None,
+                map_tier: None,
+                corrupted: false,
+                chaos_value: 1.0,
+            }]
+        });
+        PriceTable { by_name }
+    }
+
+    /// One overview export, or a directory of them (every `*.json`).
+    pub fn load(path: &Path) -> anyhow::Result<PriceTable> {
+        let files: Vec<PathBuf> = if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .with_context(|| format!("failed to read {}", path.display()))?
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "json"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut lines = Vec::new();
+        for file in &files {
+            let bytes = std::fs::read(file)
+                .with_context(|| format!("failed to read price table {}", file.display()))?;
+            match serde_json::from_slice(&bytes)
+                .with_context(|| format!("{} is not a poe.ninja export", file.display()))?
+            {
+                PriceFile::Overview { lines: l } | PriceFile::Lines(l) => lines.extend(l),
+            }
+        }
+        if lines.is_empty() {
+            anyhow::bail!(
+                "No prices in {} — download poe.ninja overview exports there",
+                path.display()
+            );
+        }
+        Ok(PriceTable::from_lines(lines))
+    }
+
+    /// Chaos value of the whole stack, or None when nothing matches.
+    pub fn price(&self, item: &Valued) -> Option<f64> {
+        let unit = match item.category {
+            Category::Map => self.map_line(item)?,
+            _ => {
+                let lines = self.by_name.get(&item.name)?;
+                match item.category {
+                    Category::Unique => unique_line(lines, item)?,
+                    Category::Gem => gem_line(lines, item)?,
+                    _ => plain(lines)?,
+                }
+            }
+        };
+        Some(unit.chaos_value * f64::from(item.stack))
+    }
+
+    /// Maps match on tier, by the table name equal to the item's base (see
+    /// `map_bases`).
+    fn map_line(&self, item: &Valued) -> Option<&PriceLine> {
+        let lines = map_bases(&item.base, item.magic).find_map(|base| self.by_name.get(base))?;
+        let tiered: Vec<&PriceLine> = lines
+            .iter()
+            .filter(|l| l.map_tier.is_none_or(|t| t == item.map_tier))
+            .collect();
+        prefer_plain(tiered)
+    }
+}
+
+/// The names a map's base line may be priced under, best first: the line
+/// itself, then — for a magic map, whose one name line carries its affixes
+/// (`Fecund Strand Map of Bameth`) — the line cut after its `Map` word,
+/// with and without the single prefix word. Other maps only match exactly.
+fn map_bases(line: &str, magic: bool) -> impl Iterator<Item = &str> {
+    let end = line
+        .match_indices(" Map")
+        .map(|(i, m)| i + m.len())
+        .find(|&i| line[i..].is_empty() || line[i..].starts_with(' '))
+        .unwrap_or(line.len());
+    let head = &line[..end];
+    let unprefixed = head.split_once(' ').map(|(_, rest)| rest);
+    let affixed = [Some(head).filter(|h| *h != line), unprefixed];
+    std::iter::once(line).chain(affixed.into_iter().flatten().filter(move |_| magic))
+}
+
+/// Lines without a variant and uncorrupted win ties.
+fn prefer_plain<'a>(lines: impl IntoIterator<Item = &'a PriceLine>) -> Option<&'a PriceLine> {
+    lines
+        .into_iter()
+        .min_by_key(|l| (l.variant.is_some(), l.corrupted))
+}
+
+fn plain(lines: &[PriceLine]) -> Option<&PriceLine> {
+    prefer_plain(lines)
+}
+
+/// poe.ninja prices uniques at 0 (under five links), 5 and 6 links. An
+/// item without a priced link count of its own falls back to the 0-link
+/// price.
+fn unique_line<'a>(lines: &'a [PriceLine], item: &Valued) -> Option<&'a PriceLine> {
+    let bucket = if item.links >= 5 { item.links } else { 0 };
+    let same_base = |l: &&PriceLine| {
+        l.base_type
+            .as_deref()
+            .is_none_or(|b| item.base.is_empty() || b == item.base)
+    };
+    let linked = |n: u8| {
+        prefer_plain(
+            lines
+                .iter()
+                .filter(same_base)
+                .filter(move |l| l.links.unwrap_or(0) == n),
+        )
+    };
+    linked(bucket).or_else(|| linked(0))
+}
+
+/// Exact level and quality, else the best listed gem the item matches or
+/// beats (a 20/17 gem is worth at least the 20/0 line).
+fn gem_line<'a>(lines: &'a [PriceLine], item: &Valued) -> Option<&'a PriceLine> {
+    let level = |l: &PriceLine| l.gem_level.unwrap_or(1);
+    let quality = |l: &PriceLine| l.gem_quality.unwrap_or(0);
+    prefer_plain(
+        lines
+            .iter()
+            .filter(|l| level(l) == item.gem_level && quality(l) == item.gem_quality),
+    )
+    .or_else(|| {
+        lines
+            .iter()
+            .filter(|l| {
+                !l.corrupted && level(l) <= item.gem_level && quality(l) <= item.gem_quality
+            })
+            .max_by_key(|l| (level(l), quality(l)))
+    })
+}
+
+/// The text `value` prints: total, per-category totals, the `top` most
+/// valuable items and every item without a price.
+pub fn report(items: &[Valued], table: &PriceTable, top: usize) -> String {
+    let mut priced: Vec<(f64, &Valued)> = Vec::new();
+    let mut unpriced: Vec<(&str, u32)> = Vec::new();
+    let mut by_category: Vec<(Category, f64)> = Vec::new();
+    for item in items {
+        let Some(value) = table.price(item) else {
+            match unpriced.iter_mut().find(|(n, _)| *n == item.name) {
+                Some((_, count)) => *count += item.stack,
+                None => unpriced.push((&item.name, item.stack)),
+            }
+            continue;
+        };
+        priced.push((value, item));
+        match by_category.iter_mut().find(|(c, _)| *c == item.category) {
+            Some((_, sum)) => *sum += value,
+            None => by_category.push((item.category, value)),
+        }
+    }
+    by_category.sort_by(|a, b| b.1.total_cmp(&a.1));
+    priced.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let total: f64 = priced.iter().map(|(v, _)| v).sum();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Total: {total:.1} chaos ({} of {} items priced)",
+        priced.len(),
+        items.len()
+    );
+    for (category, sum) in &by_category {
+        let _ = writeln!(out, "  {:<16} {sum:>10.1}", format!("{category:?}"));
+    }
+    let _ = writeln!(out, "Top {}:", top.min(priced.len()));
+    for (value, item) in priced.iter().take(top) {
+        let stack = if item.stack > 1 {
+            format!(" x{}", item.stack)
+        } else {
+            String::new()
+        };
+        let _ = writeln!(out, "  {value:>10.1}  {}{stack}", item.name);
+    }
+    if !unpriced.is_empty() {
+        let _ = writeln!(out, "Unpriced ({}):", unpriced.len());
+        for (name, count) in &unpriced {
+            let _ = writeln!(out, "  {name} x{count}");
+        }
+    }
+    out
+}
+
+/// Items from `--file`: a saved stash API response (bare, or a stash-cache
+/// entry) or a `stash copy` text dump.
+fn load_items(path: &Path) -> anyhow::Result<Vec<Valued>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Ok(parse_copy(&text));
+    };
+    let body = json.get("data").unwrap_or(&json);
+    let items = body
+        .get("items")
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("{} has no stash items list", path.display()))?;
+    let items: Vec<ApiItem> = serde_json::from_value(items)
+        .with_context(|| format!("{} has malformed items", path.display()))?;
+    Ok(items.iter().map(Valued::from).collect())
+}
+
+/// Where `value` reads items from.
+pub enum ValueSource {
+    Tab(String),
+    File(PathBuf),
+}
+
+/// `value`: price one tab (or dump) and print the report.
+pub fn run(
+    cr_config: Option<&ChaosRecipe>,
+    source: &ValueSource,
+    prices: &Path,
+    top: usize,
+) -> anyhow::Result<()> {
+    let table = PriceTable::load(prices)?;
+    let items = match source {
+        ValueSource::File(path) => load_items(path)?,
+        ValueSource::Tab(name) => {
+            let cr = cr_config.ok_or_else(|| anyhow::anyhow!("No chaos recipe config found"))?;
+            chaos_recipe::tab_items::<ApiItem>(cr, name)?
+                .iter()
+                .map(Valued::from)
+                .collect()
+        }
+    };
+    print!("{}", report(&items, &table, top));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICES: &str = r#"[
+        {"currencyTypeName": "Divine Orb", "chaosEquivalent": 150.0},
+        {"name": "Sacrifice at Dawn", "chaosValue": 2.5},
+        {"name": "The Doctor", "chaosValue": 900.0},
+        {"name": "Tabula Rasa", "baseType": "Simple Robe", "links": 6, "chaosValue": 10.0},
+        {"name": "Goldwyrm", "baseType": "Nubuck Boots", "chaosValue": 3.0},
+        {"name": "Goldwyrm", "baseType": "Nubuck Boots", "links": 5, "chaosValue": 40.0},
+        {"name": "Enlighten Support", "gemLevel": 3, "gemQuality": 0, "chaosValue": 200.0},
+        {"name": "Enlighten Support", "gemLevel": 4, "gemQuality": 0, "chaosValue": 800.0},
+        {"name": "Enlighten Support", "gemLevel": 4, "gemQuality": 0, "corrupted": true, "chaosValue": 500.0},
+        {"name": "Strand Map", "mapTier": 16, "chaosValue": 4.0},
+        {"name": "Strand Map", "mapTier": 16, "variant": "Gen-23", "chaosValue": 9.0}
+    ]"#;
+
+    fn table() -> PriceTable {
+        PriceTable::from_lines(serde_json::from_str::<Vec<PriceLine>>(PRICES).unwrap())
+    }
+
+    fn api(json: &str) -> Valued {
+        Valued::from(&serde_json::from_str::<ApiItem>(json).unwrap())
+    }
+
+    #[test]
+    fn api_items_are_priced_by_category_rules() {
+        let t = table();
+        let divines = api(
+            r#"{"frameType": 5, "typeLine": "Divine Orb", "stackSize": 3, "maxStackSize": 10}"#,
+        );
+        assert_eq!(t.price(&divines), Some(450.0));
+        let dawn = api(
+            r#"{"frameType": 0, "typeLine": "Sacrifice at Dawn", "stackSize": 4, "maxStackSize": 10}"#,
+        );
+        assert_eq!(dawn.category, Category::Fragment);
+        assert_eq!(t.price(&dawn), Some(10.0));
+
+        let boots = |links: &str| {
+            api(&format!(
+                r#"{{"frameType": 3, "name": "<<set:MS>><<set:M>><<set:S>>Goldwyrm", "typeLine": "Nubuck Boots", "baseType": "Nubuck Boots", "sockets": [{links}]}}"#
+            ))
+        };
+        let s = r#"{"group": 0}"#;
+        assert_eq!(t.price(&boots(s)), Some(3.0));
+        assert_eq!(t.price(&boots(&[s; 5].join(","))), Some(40.0));
+        // No 6-link line: fall back to the unlinked price.
+        assert_eq!(t.price(&boots(&[s; 6].join(","))), Some(3.0));
+
+        let gem = |level: &str| {
+            api(&format!(
+                r#"{{"frameType": 4, "typeLine": "Enlighten Support", "properties": [
+                    {{"name": "Level", "values": [["{level}", 0]]}},
+                    {{"name": "Quality", "values": [["+5%", 1]]}}]}}"#
+            ))
+        };
+        assert_eq!(t.price(&gem("4 (Max)")), Some(800.0));
+        assert_eq!(t.price(&gem("2")), None);
+
+        let map = api(
+            r#"{"frameType": 1, "typeLine": "Fecund Strand Map of Bameth", "baseType": "Strand Map", "properties": [{"name": "Map Tier", "values": [["16", 0]]}]}"#,
+        );
+        assert_eq!(map.category, Category::Map);
+        assert_eq!(t.price(&map), Some(4.0));
+    }
+
+    #[test]
+    fn maps_match_the_exact_base_not_a_shorter_name_inside_it() {
+        let t = PriceTable::from_lines(
+            serde_json::from_str::<Vec<PriceLine>>(
+                r#"[
+                    {"name": "Pit Map", "mapTier": 14, "chaosValue": 1.0},
+                    {"name": "Pit of the Chimera Map", "chaosValue": 30.0},
+                    {"name": "Temple Map", "mapTier": 14, "chaosValue": 2.0}
+                ]"#,
+            )
+            .unwrap(),
+        );
+        let map = |base: &str| Valued {
+            category: Category::Map,
+            name: base.into(),
+            base: base.into(),
+            magic: base.starts_with("Fecund"),
+            stack: 1,
+            links: 0,
+            gem_level: 0,
+            gem_quality: 0,
+            map_tier: 14,
+        };
+        assert_eq!(t.price(&map("Pit of the Chimera Map")), Some(30.0));
+        assert_eq!(t.price(&map("Pit Map")), Some(1.0));
+        assert_eq!(t.price(&map("Fecund Pit Map of Bameth")), Some(1.0));
+        // Unpriced, although "Temple Map" and "Pit Map" occur inside them.
+        assert_eq!(t.price(&map("Ivory Temple Map")), None);
+        assert_eq!(t.price(&map("Fecund Ivory Temple Map of Bameth")), None);
+        assert_eq!(t.price(&map("Spider Pit Map")), None);
+    }
+
+    #[test]
+    fn stash_copy_dump_is_split_per_tooltip() {
+        let boots = include_str!("../tests/example_items/unique.txt");
+        let card = "Item Class: Divination Cards\nRarity: Divination Card\nThe Doctor\n--------\nStack Size: 2/8\n";
+        let gem = "Item Class: Support Gems\nRarity: Gem\nEnlighten Support\n--------\nLevel: 3\nQuality: +20% (augmented)\n--------\nRequirements:\nLevel: 60\n";
+        let dump = format!("{boots}\n--------\n{card}--------\n{gem}--------\n");
+        let items = parse_copy(&dump);
+        assert_eq!(items.len(), 3);
+        assert_eq!((items[0].name.as_str(), items[0].links), ("Goldwyrm", 1));
+        assert_eq!(
+            (items[1].category, items[1].stack),
+            (Category::DivinationCard, 2)
+        );
+        assert_eq!((items[2].gem_level, items[2].gem_quality), (3, 20));
+
+        let t = table();
+        let out = report(&items, &t, 2);
+        assert!(
+            out.starts_with("Total: 2003.0 chaos (3 of 3 items priced)"),
+            "{out}"
+        );
+        assert!(out.contains("     1800.0  The Doctor x2"), "{out}");
+        assert!(!out.contains("Goldwyrm"), "top 2 only: {out}");
+    }
+
+    #[test]
+    fn unknown_items_are_listed_unpriced() {
+        let t = table();
+        let wisdom = api(r#"{"frameType": 5, "typeLine": "Scroll of Wisdom", "stackSize": 40}"#);
+        let out = report(&[wisdom.clone(), wisdom], &t, 5);
+        assert!(
+            out.contains("Unpriced (1):\n  Scroll of Wisdom x80"),
+            "{out}"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Named calibration profiles: one set of regions, grids, points and pointer
+//! scale per machine or window mode (laptop windowed, desktop fullscreen, …).
+//!
+//! The live calibration stays in the top-level `Settings` fields that every
+//! macro reads; a profile is a stored copy of them. `profile use` writes the
+//! live calibration back into the active profile before loading the other
+//! one, so recalibrating never needs an explicit save. Profile regions are
+//! kept window-relative, like the config file (see `Settings::to_disk`).
+use crate::stash_grid::{MapGrid, NormalGrid, StashGrid};
+use crate::{NamedPoint, ScreenRegion, Settings, WINDOW_SIZE_SLACK};
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Profile {
+    #[serde(default)]
+    pub game_window_region: Option<ScreenRegion>,
+    /// Window bounds the regions below are relative to. Without one they
+    /// are absolute screen pixels.
+    #[serde(default)]
+    pub window_anchor: Option<ScreenRegion>,
+    #[serde(default)]
+    pub inv_region: Option<ScreenRegion>,
+    #[serde(default)]
+    pub inv_samples: Option<Vec<[u32; 3]>>,
+    #[serde(default)]
+    pub inv_sample_spread: Option<Vec<[u8; 3]>>,
+    #[serde(default)]
+    pub stash_samples: Option<Vec<[u32; 3]>>,
+    #[serde(default)]
+    pub stash_sample_spread: Option<Vec<[u8; 3]>>,
+    #[serde(default)]
+    pub map_samples: Option<Vec<[u32; 3]>>,
+    #[serde(default)]
+    pub map_sample_spread: Option<Vec<[u8; 3]>>,
+    #[serde(default)]
+    pub inv_locked: Option<Vec<bool>>,
+    #[serde(default)]
+    pub stash_region: Option<ScreenRegion>,
+    #[serde(default)]
+    pub stash_grid: Option<StashGrid>,
+    #[serde(default)]
+    pub normal_grid: Option<NormalGrid>,
+    #[serde(default)]
+    pub map_region: Option<ScreenRegion>,
+    #[serde(default)]
+    pub map_grid: Option<MapGrid>,
+    #[serde(default)]
+    pub points: Option<Vec<NamedPoint>>,
+    #[serde(default)]
+    pub pointer_scale: Option<f32>,
+}
+
+impl Profile {
+    /// Window size the profile was calibrated at, if known.
+    pub fn window_size(&self) -> Option<(u32, u32)> {
+        self.window_anchor
+            .or(self.game_window_region)
+            .map(|r| (r.width, r.height))
+    }
+}
+
+impl Settings {
+    /// The live calibration as a profile.
+    pub(crate) fn current_profile(&self) -> anyhow::Result<Profile> {
+        let d = self.to_disk()?;
+        Ok(Profile {
+            game_window_region: d.game_window_region,
+            window_anchor: d.window_anchor,
+            inv_region: d.inv_region,
+            inv_samples: d.inv_samples,
+            inv_sample_spread: d.inv_sample_spread,
+            stash_samples: d.stash_samples,
+            stash_sample_spread: d.stash_sample_spread,
+            map_samples: d.map_samples,
+            map_sample_spread: d.map_sample_spread,
+            inv_locked: d.inv_locked,
+            stash_region: d.stash_region,
+            stash_grid: d.stash_grid,
+            normal_grid: d.normal_grid,
+            map_region: d.map_region,
+            map_grid: d.map_grid,
+            points: d.points,
+            pointer_scale: d.pointer_scale,
+        })
+    }
+
+    fn load_profile(&mut self, p: Profile) {
+        self.game_window_region = p.game_window_region;
+        self.window_anchor = p.window_anchor;
+        self.inv_region = p.inv_region;
+        self.inv_samples = p.inv_samples;
+        self.inv_sample_spread = p.inv_sample_spread;
+        self.stash_samples = p.stash_samples;
+        self.stash_sample_spread = p.stash_sample_spread;
+        self.map_samples = p.map_samples;
+        self.map_sample_spread = p.map_sample_spread;
+        self.inv_locked = p.inv_locked;
+        self.stash_region = p.stash_region;
+        self.stash_grid = p.stash_grid;
+        self.normal_grid = p.normal_grid;
+        self.map_region = p.map_region;
+        self.map_grid = p.map_grid;
+        self.points = p.points;
+        self.pointer_scale = p.pointer_scale;
+        if let Some(anchor) = p.window_anchor {
+            self.shift_regions(anchor.x as i64, anchor.y as i64);
+        }
+    }
+
+    /// Store the live calibration as profile `name` and make it active.
+    pub(crate) fn save_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        self.profiles
+            .insert(name.to_string(), self.current_profile()?);
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Switch to profile `name`, first saving the live calibration into the
+    /// active profile. When no stored profile holds it (none active, or the
+    /// active one was deleted) it is saved as a new `previous` profile
+    /// instead, whose name is returned, so switching never drops it.
+    pub(crate) fn use_profile(&mut self, name: &str) -> anyhow::Result<Option<String>> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            bail!("No profile named '{name}' — run: little_oil profile list");
+        };
+        let kept = match self.active_profile.clone() {
+            Some(active) if self.profiles.contains_key(&active) => {
+                if active != name {
+                    self.save_profile(&active)?;
+                }
+                None
+            }
+            _ => {
+                let previous = (1..)
+                    .map(|n| match n {
+                        1 => "previous".to_string(),
+                        n => format!("previous-{n}"),
+                    })
+                    .find(|p| !self.profiles.contains_key(p))
+                    .expect("some name is free");
+                self.save_profile(&previous)?;
+                Some(previous)
+            }
+        };
+        self.load_profile(profile);
+        self.active_profile = Some(name.to_string());
+        Ok(kept)
+    }
+
+    pub(crate) fn copy_profile(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        if self.profiles.contains_key(to) {
+            bail!("Profile '{to}' already exists — run: little_oil profile delete {to}");
+        }
+        let Some(profile) = self.profiles.get(from).cloned() else {
+            bail!("No profile named '{from}' — run: little_oil profile list");
+        };
+        self.profiles.insert(to.to_string(), profile);
+        Ok(())
+    }
+
+    pub(crate) fn delete_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.profiles.remove(name).is_none() {
+            bail!("No profile named '{name}' — run: little_oil profile list");
+        }
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        Ok(())
+    }
+
+    /// A profile calibrated for a window the size of `found`, when the live
+    /// calibration is not. Used to switch automatically at macro start.
+    pub(crate) fn profile_for_window(&self, found: ScreenRegion) -> Option<String> {
+        let fits = |(w, h): (u32, u32)| {
+            w.abs_diff(found.width) <= WINDOW_SIZE_SLACK
+                && h.abs_diff(found.height) <= WINDOW_SIZE_SLACK
+        };
+        let live = self.window_anchor.map(|r| (r.width, r.height));
+        if live.is_none_or(fits) {
+            return None;
+        }
+        self.profiles
+            .iter()
+            .find(|(_, p)| p.window_size().is_some_and(fits))
+            .map(|(name, _)| name.clone())
+    }
+}
+
+/// `profile list`: every profile with its window size, the active one
+/// starred.
+pub fn list(s: &Settings) {
+    if s.profiles.is_empty() {
+        println!("No profiles — save the current calibration with: little_oil profile save <name>");
+        return;
+    }
+    for (name, p) in &s.profiles {
+        let active = if s.active_profile.as_deref() == Some(name.as_str()) {
+            "*"
+        } else {
+            " "
+        };
+        let size = p
+            .window_size()
+            .map_or("window not set".to_string(), |(w, h)| format!("{w}x{h}"));
+        let points = p.points.as_ref().map_or(0, |ps| ps.len());
+        println!("{active} {name:<16} {size:<14} {points} points");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> ScreenRegion {
+        ScreenRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn calibrated(window: ScreenRegion, stash_x: u32) -> Settings {
+        let mut s = crate::default_settings();
+        s.window_anchor = Some(window);
+        s.game_window_region = Some(window);
+        s.stash_region = Some(region(window.x + stash_x, window.y + 160, 632, 632));
+        s
+    }
+
+    #[test]
+    fn switching_profiles_keeps_live_edits() {
+        let mut s = calibrated(region(0, 0, 1920, 1080), 16);
+        s.save_profile("desktop").unwrap();
+        let laptop = calibrated(region(10, 10, 1280, 720), 11);
+        s.load_profile(laptop.current_profile().unwrap());
+        s.save_profile("laptop").unwrap();
+
+        s.use_profile("desktop").unwrap();
+        assert_eq!(s.stash_region.unwrap().x, 16);
+        s.stash_region.as_mut().unwrap().x = 20;
+        s.use_profile("laptop").unwrap();
+        assert_eq!(s.stash_region.unwrap().x, 21);
+        s.use_profile("desktop").unwrap();
+        assert_eq!(s.stash_region.unwrap().x, 20);
+
+        assert!(s.copy_profile("laptop", "desktop").is_err());
+        s.copy_profile("laptop", "laptop-2").unwrap();
+        s.delete_profile("desktop").unwrap();
+        assert_eq!(s.active_profile, None);
+        assert!(s.use_profile("desktop").is_err());
+    }
+
+    #[test]
+    fn switching_away_from_an_unsaved_calibration_keeps_it() {
+        let mut s = calibrated(region(0, 0, 1920, 1080), 16);
+        let laptop = calibrated(region(0, 0, 1280, 720), 11);
+        s.profiles
+            .insert("laptop".into(), laptop.current_profile().unwrap());
+
+        // Nothing active: the live calibration is kept as "previous".
+        assert_eq!(
+            s.use_profile("laptop").unwrap().as_deref(),
+            Some("previous")
+        );
+        assert_eq!(s.stash_region.unwrap().x, 11);
+        s.use_profile("previous").unwrap();
+        assert_eq!(s.stash_region.unwrap().x, 16);
+
+        // The active profile was deleted: kept under a fresh name.
+        s.stash_region.as_mut().unwrap().x = 30;
+        s.delete_profile("previous").unwrap();
+        s.profiles.insert("previous".into(), Profile::default());
+        assert_eq!(
+            s.use_profile("laptop").unwrap().as_deref(),
+            Some("previous-2")
+        );
+        s.use_profile("previous-2").unwrap();
+        assert_eq!(s.stash_region.unwrap().x, 30);
+
+        // Switching between stored profiles saves nothing new.
+        assert_eq!(s.use_profile("laptop").unwrap(), None);
+        assert_eq!(s.profiles.len(), 3);
+    }
+
+    #[test]
+    fn profiles_are_picked_by_window_size() {
+        let mut s = calibrated(region(0, 0, 1920, 1080), 16);
+        s.save_profile("desktop").unwrap();
+        let laptop = calibrated(region(0, 0, 1280, 720), 11);
+        s.profiles
+            .insert("laptop".into(), laptop.current_profile().unwrap());
+
+        assert_eq!(s.profile_for_window(region(300, 0, 1920, 1080)), None);
+        assert_eq!(
+            s.profile_for_window(region(0, 0, 1281, 720)).as_deref(),
+            Some("laptop")
+        );
+        assert_eq!(s.profile_for_window(region(0, 0, 2560, 1440)), None);
+    }
+}
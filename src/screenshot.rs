@@ -82,6 +82,7 @@ pub fn diff_clusters(
     other: &ScreenshotData,
     bounds: Rect,
     min_pixels: u32,
+    matcher: crate::colour::Matcher,
 ) -> anyhow::Result<Vec<Rect>> {
     use anyhow::bail;
     use std::collections::HashMap;
@@ -129,7 +130,7 @@ pub fn diff_clusters(
         for bx in 0..bw {
             let sx = x0 as usize + bx;
             let sy = y0 as usize + by;
-            if base.get_pixel(sx, sy) == other.get_pixel(sx, sy) {
+            if !matcher.differs(base.get_pixel(sx, sy), other.get_pixel(sx, sy)) {
                 continue;
             }
 
@@ -228,6 +229,7 @@ fn union(parent: &mut [u32], a: u32, b: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::colour::Matcher;
 
     fn make_frame(w: usize, h: usize, ox: i32, oy: i32, fill: u32) -> ScreenshotData {
         let mut pixels = vec![0u8; w * h * 4];
@@ -275,7 +277,7 @@ mod tests {
             width: 40,
             height: 40,
         };
-        let clusters = diff_clusters(&base, &other, bounds, 20).unwrap();
+        let clusters = diff_clusters(&base, &other, bounds, 20, Matcher::default()).unwrap();
         assert_eq!(
             clusters.len(),
             1,
@@ -329,7 +331,7 @@ mod tests {
             width: 40,
             height: 40,
         };
-        let clusters = diff_clusters(&base, &other, bounds, 4).unwrap();
+        let clusters = diff_clusters(&base, &other, bounds, 4, Matcher::default()).unwrap();
         assert_eq!(
             clusters.len(),
             1,
@@ -358,7 +360,7 @@ mod tests {
             width: 40,
             height: 40,
         };
-        assert!(diff_clusters(&base, &other, bounds, 1).is_err());
+        assert!(diff_clusters(&base, &other, bounds, 1, Matcher::default()).is_err());
     }
 
     #[test]
@@ -371,7 +373,7 @@ mod tests {
             width: 40,
             height: 40,
         };
-        assert!(diff_clusters(&base, &other, bounds, 1).is_err());
+        assert!(diff_clusters(&base, &other, bounds, 1, Matcher::default()).is_err());
     }
 
     #[test]
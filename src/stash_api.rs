@@ -0,0 +1,405 @@
+//! OAuth stash backend for `api.pathofexile.com`: tab listing, per-tab fetch
+//! by id, and `X-Rate-Limit-*` / `Retry-After` handling.
+//!
+//! The legacy `character-window/get-stash-items` endpoint (POESESSID cookie)
+//! stays in `chaos_recipe`; this is the path used when an OAuth token is set.
+//! Both send their requests through [`get_paced`].
+use anyhow::bail;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use ureq::RequestBuilder;
+use ureq::http::HeaderMap;
+use ureq::typestate::WithoutBody;
+
+/// Production API root. `ChaosRecipe::api_base` overrides it (tests point it
+/// at a local mock server).
+pub const API_BASE: &str = "https://api.pathofexile.com";
+
+/// Attempts per request before a 429 is surfaced as an error.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Longest single rate-limit wait we are willing to sleep through. Anything
+/// longer is a lockout the user should know about, not wait out silently.
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+/// One entry of `GET /stash/<league>`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StashTabInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub index: Option<usize>,
+    #[serde(default)]
+    pub metadata: TabMetadata,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TabMetadata {
+    /// Hex colour without `#`, e.g. `"7c5436"`.
+    #[serde(default)]
+    pub colour: Option<String>,
+}
+
+/// `GET /stash/<league>/<id>` body, generic over the item type so the
+/// recipe code can deserialize straight into its own item struct.
+#[allow(dead_code)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct StashContents<I> {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub index: Option<usize>,
+    #[serde(default = "Vec::new")]
+    pub items: Vec<I>,
+}
+
+#[derive(Deserialize)]
+struct StashList {
+    stashes: Vec<StashTabInfo>,
+}
+
+#[derive(Deserialize)]
+struct StashOne<I> {
+    stash: StashContents<I>,
+}
+
+/// An authenticated client for one league.
+pub struct OAuthStash<'a> {
+    pub base: &'a str,
+    pub token: &'a str,
+    pub league: &'a str,
+}
+
+impl OAuthStash<'_> {
+    /// Every top-level stash tab in the league.
+    pub fn list_tabs(&self) -> anyhow::Result<Vec<StashTabInfo>> {
+        let list: StashList = self.get(&format!("/stash/{}", encode_segment(self.league)))?;
+        Ok(list.stashes)
+    }
+
+    /// One tab's contents by its API id.
+    pub fn fetch_tab<I: DeserializeOwned>(&self, id: &str) -> anyhow::Result<StashContents<I>> {
+        let one: StashOne<I> = self.get(&format!(
+            "/stash/{}/{}",
+            encode_segment(self.league),
+            encode_segment(id)
+        ))?;
+        Ok(one.stash)
+    }
+
+    /// GET `path` through [`get_paced`].
+    fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let url = format!("{}{}", self.base.trim_end_matches('/'), path);
+        let request = || {
+            ureq::get(&url)
+                .header("Accept", "application/json")
+                .header("Authorization", &format!("Bearer {}", self.token))
+                .header(
+                    "User-Agent",
+                    &format!("OAuth little_oil/{}", env!("CARGO_PKG_VERSION")),
+                )
+        };
+        get_paced(&url, request, |status| match status {
+            401 | 403 => anyhow::anyhow!(
+                "{url} refused the OAuth token (HTTP {status}) — refresh oauth_token in config.json"
+            ),
+            _ => anyhow::anyhow!("{url} returned HTTP {status}"),
+        })
+    }
+}
+
+/// Send the GET `request` builds and parse its JSON body, waiting out rate
+/// limits. A 429 is retried after the server's `Retry-After` (or the
+/// exceeded window) up to `MAX_ATTEMPTS` times; a successful response whose
+/// state headers say the next call would be limited also waits here, so
+/// callers never trip the limit. A wait over `MAX_WAIT` on either is an
+/// error naming it. Other non-2xx statuses become `status_error`; `what`
+/// names the endpoint in messages.
+pub(crate) fn get_paced<T: DeserializeOwned>(
+    what: &str,
+    request: impl Fn() -> RequestBuilder<WithoutBody>,
+    status_error: impl Fn(u16) -> anyhow::Error,
+) -> anyhow::Result<T> {
+    for attempt in 1..=MAX_ATTEMPTS {
+        let resp = request()
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .call()
+            .map_err(|e| anyhow::anyhow!("failed to reach {what}: {e}"))?;
+        let status = resp.status().as_u16();
+        let wait = rate_limit_wait(resp.headers());
+        if status == 429 {
+            let wait = wait.unwrap_or(Duration::from_secs(1));
+            if attempt == MAX_ATTEMPTS || wait > MAX_WAIT {
+                bail!(
+                    "rate limited by {what} (retry after {}s) — wait and run again",
+                    wait.as_secs()
+                );
+            }
+            tracing::warn!(?wait, attempt, "{what} rate limited; waiting");
+            std::thread::sleep(wait);
+            continue;
+        }
+        if !(200..300).contains(&status) {
+            return Err(status_error(status));
+        }
+        let body: T = resp
+            .into_body()
+            .read_json()
+            .map_err(|e| anyhow::anyhow!("failed to parse {what} JSON: {e}"))?;
+        if let Some(wait) = wait {
+            if wait > MAX_WAIT {
+                bail!(
+                    "{what} rate limit window is full for {}s — wait and run again",
+                    wait.as_secs()
+                );
+            }
+            tracing::info!(?wait, "{what} window full; pacing next request");
+            std::thread::sleep(wait);
+        }
+        return Ok(body);
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// Percent-encode the characters league names and ids actually contain.
+fn encode_segment(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+/// `max:period:restricted` triples from one `X-Rate-Limit-<rule>` header,
+/// or `current:period:active_restriction` from its `-State` twin.
+fn parse_windows(value: &str) -> Vec<(u64, u64, u64)> {
+    value
+        .split(',')
+        .filter_map(|w| {
+            let mut it = w.trim().split(':').map(|n| n.parse::<u64>().ok());
+            Some((it.next()??, it.next()??, it.next()??))
+        })
+        .collect()
+}
+
+/// How long to wait before the next request, from a response's headers.
+/// `Retry-After` wins; otherwise every rule in `X-Rate-Limit-Rules` is
+/// checked window by window: an active restriction waits it out, and a
+/// window whose hit count reached its maximum waits a full period.
+pub fn rate_limit_wait(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(secs) = header("retry-after").and_then(|v| v.trim().parse::<u64>().ok()) {
+        return Some(Duration::from_secs(secs));
+    }
+    let mut wait = 0;
+    for rule in header("x-rate-limit-rules").unwrap_or("").split(',') {
+        let rule = rule.trim().to_ascii_lowercase();
+        if rule.is_empty() {
+            continue;
+        }
+        let limits = parse_windows(header(&format!("x-rate-limit-{rule}")).unwrap_or(""));
+        let state = parse_windows(header(&format!("x-rate-limit-{rule}-state")).unwrap_or(""));
+        for ((max, period, _), (current, _, restricted)) in limits.iter().zip(&state) {
+            if *restricted > 0 {
+                wait = wait.max(*restricted);
+            } else if current >= max {
+                wait = wait.max(*period);
+            }
+        }
+    }
+    (wait > 0).then(|| Duration::from_secs(wait))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, mock_http};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        for (k, v) in pairs {
+            h.insert(*k, v.parse().unwrap());
+        }
+        h
+    }
+
+    /// Recorded `GET /stash/<league>` response (trimmed).
+    const LIST_FIXTURE: &str = r#"{"stashes": [
+        {"id": "a1b2c3", "name": "dump", "type": "QuadStash", "index": 0, "metadata": {"colour": "7c5436"}},
+        {"id": "d4e5f6", "name": "chaos", "type": "PremiumStash", "index": 1, "metadata": {}}
+    ]}"#;
+
+    #[test]
+    fn rate_limit_wait_prefers_retry_after() {
+        let h = headers(&[("retry-after", "7"), ("x-rate-limit-rules", "Ip")]);
+        assert_eq!(rate_limit_wait(&h), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn rate_limit_wait_reads_rule_windows() {
+        let under = headers(&[
+            ("x-rate-limit-rules", "Ip,Account"),
+            ("x-rate-limit-ip", "45:60:60,240:240:900"),
+            ("x-rate-limit-ip-state", "3:60:0,10:240:0"),
+            ("x-rate-limit-account", "30:60:60"),
+            ("x-rate-limit-account-state", "1:60:0"),
+        ]);
+        assert_eq!(rate_limit_wait(&under), None);
+
+        let full = headers(&[
+            ("x-rate-limit-rules", "Ip,Account"),
+            ("x-rate-limit-ip", "45:60:60,240:240:900"),
+            ("x-rate-limit-ip-state", "3:60:0,240:240:0"),
+            ("x-rate-limit-account", "30:60:60"),
+            ("x-rate-limit-account-state", "1:60:0"),
+        ]);
+        assert_eq!(rate_limit_wait(&full), Some(Duration::from_secs(240)));
+
+        let restricted = headers(&[
+            ("x-rate-limit-rules", "Account"),
+            ("x-rate-limit-account", "30:60:60"),
+            ("x-rate-limit-account-state", "31:60:42"),
+        ]);
+        assert_eq!(rate_limit_wait(&restricted), Some(Duration::from_secs(42)));
+    }
+
+    #[test]
+    fn list_tabs_parses_fixture_and_encodes_league() {
+        let (base, requests) = mock_http(vec![MockResponse::ok(LIST_FIXTURE)]);
+        let api = OAuthStash {
+            base: &base,
+            token: "tok",
+            league: "Settlers of Kalguur",
+        };
+        let tabs = api.list_tabs().unwrap();
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[0].kind, "QuadStash");
+        assert_eq!(tabs[0].metadata.colour.as_deref(), Some("7c5436"));
+        let seen = requests.lock();
+        assert!(seen[0].starts_with("GET /stash/Settlers%20of%20Kalguur "));
+        assert!(seen[0].to_lowercase().contains("authorization: bearer tok"));
+    }
+
+    #[test]
+    fn retries_after_429() {
+        let (base, requests) = mock_http(vec![
+            MockResponse {
+                status: 429,
+                headers: vec![("Retry-After", "1")],
+                body: "{}",
+            },
+            MockResponse::ok(LIST_FIXTURE),
+        ]);
+        let api = OAuthStash {
+            base: &base,
+            token: "tok",
+            league: "Standard",
+        };
+        assert_eq!(api.list_tabs().unwrap().len(), 2);
+        assert_eq!(requests.lock().len(), 2);
+    }
+
+    #[test]
+    fn get_paced_retries_a_429_then_returns_the_body() {
+        let (base, requests) = mock_http(vec![
+            MockResponse {
+                status: 429,
+                headers: vec![("Retry-After", "1")],
+                body: "{}",
+            },
+            MockResponse::ok(r#"{"n": 7}"#),
+        ]);
+        let started = std::time::Instant::now();
+        let body: serde_json::Value =
+            get_paced("mock", || ureq::get(&base), |s| anyhow::anyhow!("HTTP {s}")).unwrap();
+        assert_eq!(body["n"], 7);
+        assert_eq!(requests.lock().len(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn get_paced_waits_out_a_full_window_before_returning() {
+        let (base, _) = mock_http(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![
+                    ("X-Rate-Limit-Rules", "Ip"),
+                    ("X-Rate-Limit-Ip", "5:1:60"),
+                    ("X-Rate-Limit-Ip-State", "5:1:0"),
+                ],
+                body: "{}",
+            },
+            MockResponse {
+                status: 500,
+                headers: Vec::new(),
+                body: "{}",
+            },
+        ]);
+        let started = std::time::Instant::now();
+        let _: serde_json::Value =
+            get_paced("mock", || ureq::get(&base), |s| anyhow::anyhow!("HTTP {s}")).unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        let err = get_paced::<serde_json::Value>(
+            "mock",
+            || ureq::get(&base),
+            |s| anyhow::anyhow!("HTTP {s}"),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "HTTP 500");
+    }
+
+    #[test]
+    fn get_paced_reports_a_window_longer_than_max_wait() {
+        // A 15 minute restriction on a successful response: the next call
+        // would be refused, so say so instead of sending it.
+        let (base, _) = mock_http(vec![MockResponse {
+            status: 200,
+            headers: vec![
+                ("X-Rate-Limit-Rules", "Account"),
+                ("X-Rate-Limit-Account", "30:60:900"),
+                ("X-Rate-Limit-Account-State", "31:60:900"),
+            ],
+            body: "{}",
+        }]);
+        let started = std::time::Instant::now();
+        let err = get_paced::<serde_json::Value>(
+            "mock",
+            || ureq::get(&base),
+            |s| anyhow::anyhow!("HTTP {s}"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("full for 900s"), "{err}");
+        assert!(started.elapsed() < MAX_WAIT);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let limited = || MockResponse {
+            status: 429,
+            headers: vec![("Retry-After", "0")],
+            body: "{}",
+        };
+        let (base, _) = mock_http(vec![limited(), limited(), limited()]);
+        let api = OAuthStash {
+            base: &base,
+            token: "tok",
+            league: "Standard",
+        };
+        let err = api.list_tabs().unwrap_err().to_string();
+        assert!(err.contains("rate limited"), "{err}");
+    }
+}
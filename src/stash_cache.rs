@@ -0,0 +1,162 @@
+//! On-disk copies of stash API responses, so the recipe, tally and filter
+//! commands can run from a recent or hand-edited snapshot instead of
+//! pathofexile.com.
+//!
+//! Layout: `stash-cache/<league>/<unix seconds>.json`, one [`CacheEntry`] per
+//! successful fetch. Only the newest `KEEP` entries per league are kept.
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept per league; older ones are deleted on save.
+const KEEP: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheEntry<T> {
+    /// Unix seconds.
+    pub saved_at: u64,
+    /// Which tabs the response covers, so a different tab list never reads
+    /// another list's snapshot.
+    pub tabs: Vec<String>,
+    pub data: T,
+}
+
+impl<T> CacheEntry<T> {
+    pub fn age_secs(&self) -> u64 {
+        now().saturating_sub(self.saved_at)
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// League names contain spaces; keep the directory name to safe characters.
+pub(crate) fn league_dir(root: &Path, league: &str) -> PathBuf {
+    let name: String = league
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    root.join(name)
+}
+
+/// Cache files for `league`, newest first.
+fn entries(root: &Path, league: &str) -> Vec<(u64, PathBuf)> {
+    let Ok(dir) = std::fs::read_dir(league_dir(root, league)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(u64, PathBuf)> = dir
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            let stamp = path.file_stem()?.to_str()?.parse().ok()?;
+            (path.extension()? == "json").then_some((stamp, path))
+        })
+        .collect();
+    files.sort_by_key(|f| std::cmp::Reverse(f.0));
+    files
+}
+
+/// Save `data` as the newest entry for `league` and prune old ones.
+pub fn save<T: Serialize>(
+    root: &Path,
+    league: &str,
+    tabs: &[&str],
+    data: &T,
+) -> anyhow::Result<PathBuf> {
+    let dir = league_dir(root, league);
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let entry = CacheEntry {
+        saved_at: now(),
+        tabs: tabs.iter().map(|t| t.to_string()).collect(),
+        data,
+    };
+    let path = dir.join(format!("{}.json", entry.saved_at));
+    std::fs::write(&path, serde_json::to_vec(&entry)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    for (_, old) in entries(root, league).into_iter().skip(KEEP) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// The newest readable entry for `league` covering exactly `tabs`.
+pub fn latest<T: DeserializeOwned>(
+    root: &Path,
+    league: &str,
+    tabs: &[&str],
+) -> Option<CacheEntry<T>> {
+    entries(root, league).into_iter().find_map(|(_, path)| {
+        let entry: CacheEntry<T> = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+        (entry.tabs == tabs).then_some(entry)
+    })
+}
+
+/// Read `--stash-file`: a cache entry, or a bare API response saved by hand.
+pub fn load_file<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if let Ok(entry) = serde_json::from_slice::<CacheEntry<T>>(&bytes) {
+        return Ok(entry.data);
+    }
+    serde_json::from_slice(&bytes)
+        .with_context(|| format!("{} is not a stash snapshot", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn newest_entry_for_the_same_tabs_wins() {
+        let tmp = TempDir::new("stash-cache");
+        let root = tmp.path();
+        let dir = league_dir(root, "Settlers of Kalguur");
+        std::fs::create_dir_all(&dir).unwrap();
+        for (stamp, tabs, v) in [(100, "chaos", 1), (300, "chaos", 3), (400, "dump", 4)] {
+            let entry = CacheEntry {
+                saved_at: stamp,
+                tabs: vec![tabs.to_string()],
+                data: v,
+            };
+            std::fs::write(
+                dir.join(format!("{stamp}.json")),
+                serde_json::to_vec(&entry).unwrap(),
+            )
+            .unwrap();
+        }
+        let got: CacheEntry<u32> = latest(root, "Settlers of Kalguur", &["chaos"]).unwrap();
+        assert_eq!((got.saved_at, got.data), (300, 3));
+        assert!(latest::<u32>(root, "Standard", &["chaos"]).is_none());
+    }
+
+    #[test]
+    fn save_prunes_and_load_file_reads_both_shapes() {
+        let tmp = TempDir::new("stash-cache");
+        let root = tmp.path();
+        let dir = league_dir(root, "Standard");
+        std::fs::create_dir_all(&dir).unwrap();
+        for stamp in 0..KEEP as u64 + 3 {
+            std::fs::write(dir.join(format!("{stamp}.json")), "{}").unwrap();
+        }
+        let path = save(root, "Standard", &["chaos"], &vec![7u32]).unwrap();
+        assert_eq!(entries(root, "Standard").len(), KEEP);
+        assert_eq!(load_file::<Vec<u32>>(&path).unwrap(), [7]);
+
+        let bare = root.join("bare.json");
+        std::fs::write(&bare, "[1, 2]").unwrap();
+        assert_eq!(load_file::<Vec<u32>>(&bare).unwrap(), [1, 2]);
+    }
+}
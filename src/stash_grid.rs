@@ -1,11 +1,15 @@
-//! Calibrated grid geometry (quad 24x24, map 12x7) with search-highlight
-//! probes and detection.
+//! Calibrated grid geometry (quad 24x24, normal 12x12, map 12x7) with
+//! search-highlight probes and detection.
+use crate::colour::Matcher;
 use crate::screenshot::{Rect, ScreenshotData};
 use serde::{Deserialize, Serialize};
 
-/// Quad tab is 24x24; map tab selection grid is 12x7.
+/// Quad tab is 24x24; normal and premium tabs 12x12; map tab selection
+/// grid is 12x7.
 pub const QUAD_COLS: usize = 24;
 pub const QUAD_ROWS: usize = 24;
+pub const NORMAL_COLS: usize = 12;
+pub const NORMAL_ROWS: usize = 12;
 pub const MAP_COLS: usize = 12;
 pub const MAP_ROWS: usize = 7;
 
@@ -26,20 +30,68 @@ pub struct CellGrid<const C: usize, const R: usize> {
     pub cell_h: u32,
     /// Search-highlight border color, as packed by ScreenshotData::get_pixel.
     pub highlight_color: u32,
+    /// How far the calibration reads of the highlight strayed from
+    /// `highlight_color`; tolerated on top of `colour_tolerance`.
+    pub highlight_spread: u8,
 }
 
 pub type StashGrid = CellGrid<QUAD_COLS, QUAD_ROWS>;
+pub type NormalGrid = CellGrid<NORMAL_COLS, NORMAL_ROWS>;
 pub type MapGrid = CellGrid<MAP_COLS, MAP_ROWS>;
 
+/// The calibrated grid for one stash tab, picked by its layout: the stash
+/// API's `quadLayout` / tab type says which one applies.
+#[derive(Debug, Clone)]
+pub enum TabGrid {
+    Quad(StashGrid),
+    Normal(NormalGrid),
+}
+
+impl TabGrid {
+    /// (columns, rows).
+    pub fn dims(&self) -> (usize, usize) {
+        match self {
+            TabGrid::Quad(_) => (QUAD_COLS, QUAD_ROWS),
+            TabGrid::Normal(_) => (NORMAL_COLS, NORMAL_ROWS),
+        }
+    }
+
+    /// Middle of cell (col, row) in frame-pixel space, None off the grid.
+    pub fn cell_center(&self, col: usize, row: usize) -> Option<(u32, u32)> {
+        let (cols, rows) = self.dims();
+        if col >= cols || row >= rows {
+            return None;
+        }
+        Some(match self {
+            TabGrid::Quad(g) => g.cell_center(col, row),
+            TabGrid::Normal(g) => g.cell_center(col, row),
+        })
+    }
+
+    pub fn is_highlighted(
+        &self,
+        frame: &ScreenshotData,
+        col: usize,
+        row: usize,
+        matcher: Matcher,
+    ) -> bool {
+        match self {
+            TabGrid::Quad(g) => g.is_highlighted(frame, col, row, matcher),
+            TabGrid::Normal(g) => g.is_highlighted(frame, col, row, matcher),
+        }
+    }
+}
+
 impl<const C: usize, const R: usize> Serialize for CellGrid<C, R> {
     fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         use serde::ser::SerializeStruct;
-        let mut st = s.serialize_struct("CellGrid", 5)?;
+        let mut st = s.serialize_struct("CellGrid", 6)?;
         st.serialize_field("cols", &self.cols[..])?;
         st.serialize_field("rows", &self.rows[..])?;
         st.serialize_field("cell_w", &self.cell_w)?;
         st.serialize_field("cell_h", &self.cell_h)?;
         st.serialize_field("highlight_color", &self.highlight_color)?;
+        st.serialize_field("highlight_spread", &self.highlight_spread)?;
         st.end()
     }
 }
@@ -53,6 +105,8 @@ impl<'de, const C: usize, const R: usize> Deserialize<'de> for CellGrid<C, R> {
             cell_w: u32,
             cell_h: u32,
             highlight_color: u32,
+            #[serde(default)]
+            highlight_spread: u8,
         }
         let h = Helper::deserialize(d)?;
         let cols: [u32; C] = h.cols.try_into().map_err(|v: Vec<u32>| {
@@ -67,6 +121,7 @@ impl<'de, const C: usize, const R: usize> Deserialize<'de> for CellGrid<C, R> {
             cell_w: h.cell_w,
             cell_h: h.cell_h,
             highlight_color: h.highlight_color,
+            highlight_spread: h.highlight_spread,
         })
     }
 }
@@ -93,20 +148,57 @@ impl<const C: usize, const R: usize> CellGrid<C, R> {
         ]
     }
 
-    /// True when at least 2 of 3 probes match the calibrated highlight color.
-    /// Two-of-three tolerates one probe landing on overlapping item art.
-    /// Off-frame probes count as non-matching.
-    pub fn is_highlighted(&self, frame: &ScreenshotData, col: usize, row: usize) -> bool {
+    /// Empty-cell probes: the cell's vertical middle at 25%, 50% and 75% of
+    /// its width, like `App::inv_probes`. Item art covers the middle, the
+    /// empty-cell background shows through everywhere else.
+    pub fn interior_probes(&self, col: usize, row: usize) -> [(usize, usize); 3] {
+        let y = (self.rows[row] + self.cell_h / 2) as usize;
+        let x0 = self.cols[col];
+        [
+            ((x0 + self.cell_w / 4) as usize, y),
+            ((x0 + self.cell_w / 2) as usize, y),
+            ((x0 + self.cell_w * 3 / 4) as usize, y),
+        ]
+    }
+
+    /// True when at least 2 of 3 probes match the calibrated highlight color
+    /// within `matcher`'s tolerance plus the grid's spread. Two-of-three
+    /// tolerates one probe landing on overlapping item art. Off-frame probes
+    /// count as non-matching.
+    pub fn is_highlighted(
+        &self,
+        frame: &ScreenshotData,
+        col: usize,
+        row: usize,
+        matcher: Matcher,
+    ) -> bool {
+        let lit = |x: usize, y: usize| {
+            frame
+                .try_get_pixel(x, y)
+                .is_some_and(|c| matcher.matches(c, self.highlight_color, self.highlight_spread))
+        };
         self.probes(col, row)
             .iter()
-            .filter(|(x, y)| {
-                frame.try_get_pixel(*x, *y) == Some(self.highlight_color)
-                    || (*y > 0 && frame.try_get_pixel(*x, *y - 1) == Some(self.highlight_color))
-            })
+            .filter(|&&(x, y)| lit(x, y) || (y > 0 && lit(x, y - 1)))
             .count()
             >= 2
     }
 
+    /// Grid of square cells `pitch` apart starting at frame pixel `origin`,
+    /// for geometry that is computed rather than captured (see `layout`).
+    pub fn uniform(origin: (f64, f64), pitch: f64, highlight_color: u32) -> Self {
+        let cols: [u32; C] = std::array::from_fn(|i| (origin.0 + pitch * i as f64).round() as u32);
+        let rows: [u32; R] = std::array::from_fn(|i| (origin.1 + pitch * i as f64).round() as u32);
+        CellGrid {
+            cols,
+            rows,
+            cell_w: pitch.round() as u32,
+            cell_h: pitch.round() as u32,
+            highlight_color,
+            highlight_spread: 0,
+        }
+    }
+
     /// Build from the two calibrated corner cells by linear interpolation.
     pub fn from_corners(
         top_left: Rect,
@@ -145,6 +237,7 @@ impl<const C: usize, const R: usize> CellGrid<C, R> {
             cell_w,
             cell_h,
             highlight_color,
+            highlight_spread: 0,
         })
     }
 }
@@ -176,6 +269,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tab_grid_bounds_follow_the_layout() {
+        let tl = Rect {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+        };
+        let br = |n: u32| Rect {
+            x: 10 * n,
+            y: 10 * n,
+            ..tl
+        };
+        let quad = TabGrid::Quad(StashGrid::from_corners(tl, br(23), 0).unwrap());
+        let normal = TabGrid::Normal(NormalGrid::from_corners(tl, br(11), 0).unwrap());
+        assert_eq!(quad.cell_center(20, 20), Some((205, 205)));
+        assert_eq!(normal.cell_center(11, 11), Some((115, 115)));
+        assert_eq!(normal.cell_center(12, 0), None);
+    }
+
     #[test]
     fn test_map_grid_from_corners_bails_on_multi_cell() {
         let tl = Rect {
@@ -0,0 +1,332 @@
+//! Timestamped stash snapshots and the `diff` between two of them, for
+//! income-per-hour feedback during a grinding session.
+//!
+//! Layout: `snapshots/<league>/<unix seconds>.json.gz`, one gzip-compressed
+//! [`Snapshot`] per `snapshot` run. Snapshots older than `RETAIN_SECS` are
+//! deleted on save, and never more than `KEEP` are kept.
+use anyhow::Context;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chaos_recipe::{self, ChaosRecipe};
+use crate::pricing::{ApiItem, PriceTable, Valued};
+use crate::stash_cache::{league_dir, now};
+
+/// Snapshots kept per league at most.
+const KEEP: usize = 200;
+/// Snapshots older than this (two weeks) are deleted on save.
+const RETAIN_SECS: u64 = 14 * 24 * 3600;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    /// Unix seconds.
+    pub saved_at: u64,
+    /// Raw stash API items per tab name.
+    pub tabs: BTreeMap<String, Vec<serde_json::Value>>,
+}
+
+/// Saved snapshots for `league`, oldest first.
+fn entries(root: &Path, league: &str) -> Vec<(u64, PathBuf)> {
+    let Ok(dir) = std::fs::read_dir(league_dir(root, league)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(u64, PathBuf)> = dir
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            let name = path.file_name()?.to_str()?;
+            let stamp = name.strip_suffix(".json.gz")?.parse().ok()?;
+            Some((stamp, path))
+        })
+        .collect();
+    files.sort_by_key(|f| f.0);
+    files
+}
+
+pub fn save(root: &Path, league: &str, snapshot: &Snapshot) -> anyhow::Result<PathBuf> {
+    let dir = league_dir(root, league);
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.json.gz", snapshot.saved_at));
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&serde_json::to_vec(snapshot)?)?;
+    std::fs::write(&path, gz.finish()?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    let all = entries(root, league);
+    let excess = all.len().saturating_sub(KEEP);
+    for (i, (stamp, old)) in all.iter().enumerate() {
+        if i < excess || now().saturating_sub(*stamp) > RETAIN_SECS {
+            let _ = std::fs::remove_file(old);
+        }
+    }
+    Ok(path)
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Snapshot> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut json = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut json)
+        .with_context(|| format!("{} is not a gzip snapshot", path.display()))?;
+    serde_json::from_slice(&json).with_context(|| format!("{} is malformed", path.display()))
+}
+
+/// Stable identity of an item across snapshots: its API id, or where it
+/// sits when the response has none.
+fn item_key(tab: &str, item: &serde_json::Value) -> String {
+    match item.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => format!("{tab}:{}:{}:{}", item["x"], item["y"], item["typeLine"]),
+    }
+}
+
+fn stack(item: &serde_json::Value) -> i64 {
+    item.get("stackSize").and_then(|v| v.as_i64()).unwrap_or(1)
+}
+
+/// Net change of one item name between two snapshots, in stack units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub name: String,
+    pub delta: i64,
+    /// A representative item, for pricing one unit.
+    pub item: Valued,
+}
+
+/// Per-name unit changes from `a` to `b`. Items are matched by id, so a
+/// stack that grew counts only the growth and a moved item counts nothing.
+pub fn diff(a: &Snapshot, b: &Snapshot) -> Vec<Change> {
+    let index = |s: &Snapshot| -> HashMap<String, serde_json::Value> {
+        s.tabs
+            .iter()
+            .flat_map(|(tab, items)| items.iter().map(move |i| (item_key(tab, i), i.clone())))
+            .collect()
+    };
+    let (before, after) = (index(a), index(b));
+    let mut deltas: Vec<(serde_json::Value, i64)> = Vec::new();
+    for (key, item) in &after {
+        let old = before.get(key).map_or(0, stack);
+        deltas.push((item.clone(), stack(item) - old));
+    }
+    for (key, item) in &before {
+        if !after.contains_key(key) {
+            deltas.push((item.clone(), -stack(item)));
+        }
+    }
+
+    let mut changes: Vec<Change> = Vec::new();
+    for (raw, delta) in deltas {
+        if delta == 0 {
+            continue;
+        }
+        let Ok(api) = serde_json::from_value::<ApiItem>(raw) else {
+            continue;
+        };
+        let item = Valued::from(&api);
+        match changes.iter_mut().find(|c| c.name == item.name) {
+            Some(c) => c.delta += delta,
+            None => changes.push(Change {
+                name: item.name.clone(),
+                delta,
+                item,
+            }),
+        }
+    }
+    changes.retain(|c| c.delta != 0);
+    changes.sort_by(|x, y| y.delta.cmp(&x.delta).then_with(|| x.name.cmp(&y.name)));
+    changes
+}
+
+/// Chaos value of `delta` units of the change's item.
+fn change_value(table: &PriceTable, c: &Change) -> Option<f64> {
+    let unit = table.price(&Valued {
+        stack: 1,
+        ..c.item.clone()
+    })?;
+    Some(unit * c.delta as f64)
+}
+
+/// The text `diff` prints: added and removed items and, with prices, the
+/// net value and value per hour over the snapshots' time span.
+pub fn report(a: &Snapshot, b: &Snapshot, table: Option<&PriceTable>) -> String {
+    let changes = diff(a, b);
+    let secs = b.saved_at.saturating_sub(a.saved_at);
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} → {} ({}h {}m)",
+        a.saved_at,
+        b.saved_at,
+        secs / 3600,
+        secs % 3600 / 60
+    );
+    let mut net = 0.0;
+    for (title, added) in [("Added", true), ("Removed", false)] {
+        let part: Vec<&Change> = changes.iter().filter(|c| (c.delta > 0) == added).collect();
+        if part.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "{title} ({}):", part.len());
+        for c in part {
+            let value = table.and_then(|t| change_value(t, c));
+            net += value.unwrap_or(0.0);
+            let price = value.map_or(String::new(), |v| format!("{v:>10.1}"));
+            let _ = writeln!(out, "  {:+6} {:<32}{price}", c.delta, c.name);
+        }
+    }
+    if changes.is_empty() {
+        let _ = writeln!(out, "No changes");
+    }
+    if table.is_some() {
+        let hours = secs as f64 / 3600.0;
+        let rate = if hours > 0.0 { net / hours } else { 0.0 };
+        let _ = writeln!(out, "Net value: {net:+.1} chaos ({rate:+.1} chaos/hour)");
+    }
+    out
+}
+
+/// `snapshot <tab>...`: fetch the tabs and save them.
+pub fn take(cr_config: &ChaosRecipe, league: &str, tabs: &[String]) -> anyhow::Result<()> {
+    let mut snapshot = Snapshot {
+        saved_at: now(),
+        tabs: BTreeMap::new(),
+    };
+    for tab in tabs {
+        let items = chaos_recipe::tab_items::<serde_json::Value>(cr_config, tab)?;
+        println!("{tab}: {} items", items.len());
+        snapshot.tabs.insert(tab.clone(), items);
+    }
+    let path = save(&crate::snapshots_dir()?, league, &snapshot)?;
+    println!("Saved snapshot {} ({})", snapshot.saved_at, path.display());
+    Ok(())
+}
+
+/// `snapshot` without tabs: list what is saved.
+pub fn list(league: &str) -> anyhow::Result<()> {
+    let all = entries(&crate::snapshots_dir()?, league);
+    if all.is_empty() {
+        println!("No snapshots for {league} — run: little_oil snapshot <tab> [tab ...]");
+    }
+    for (stamp, path) in all {
+        match load(&path) {
+            Ok(s) => {
+                let tabs: Vec<String> = s
+                    .tabs
+                    .iter()
+                    .map(|(t, items)| format!("{t} ({})", items.len()))
+                    .collect();
+                println!("{stamp}  {}", tabs.join(", "));
+            }
+            Err(e) => println!("{stamp}  unreadable: {e:#}"),
+        }
+    }
+    Ok(())
+}
+
+/// `diff [from [to]]`: compare two snapshots by timestamp; by default the
+/// newest against the one before it.
+pub fn run_diff(
+    league: &str,
+    from: Option<u64>,
+    to: Option<u64>,
+    prices: Option<&Path>,
+) -> anyhow::Result<()> {
+    let all = entries(&crate::snapshots_dir()?, league);
+    let find = |stamp: u64| {
+        all.iter()
+            .find(|(s, _)| *s == stamp)
+            .map(|(_, p)| p.clone())
+            .ok_or_else(|| anyhow::anyhow!("No snapshot {stamp} — run: little_oil snapshot"))
+    };
+    let newest = |skip: usize| {
+        all.iter()
+            .rev()
+            .nth(skip)
+            .map(|(_, p)| p.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Need two snapshots to diff — run: little_oil snapshot <tab>")
+            })
+    };
+    let (a, b) = match (from, to) {
+        (Some(f), Some(t)) => (find(f)?, find(t)?),
+        (Some(f), None) => (find(f)?, newest(0)?),
+        _ => (newest(1)?, newest(0)?),
+    };
+    let table = prices.map(PriceTable::load).transpose()?;
+    print!("{}", report(&load(&a)?, &load(&b)?, table.as_ref()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::PriceLine;
+    use crate::test_support::TempDir;
+
+    fn snap(saved_at: u64, items: &str) -> Snapshot {
+        Snapshot {
+            saved_at,
+            tabs: BTreeMap::from([("dump".to_string(), serde_json::from_str(items).unwrap())]),
+        }
+    }
+
+    #[test]
+    fn diff_counts_stack_growth_and_removals_by_id() {
+        let a = snap(
+            1000,
+            r#"[{"id": "d1", "frameType": 5, "typeLine": "Divine Orb", "stackSize": 2},
+                {"id": "b1", "frameType": 3, "name": "Goldwyrm", "typeLine": "Nubuck Boots"}]"#,
+        );
+        let b = snap(
+            1000 + 1800,
+            r#"[{"id": "d1", "frameType": 5, "typeLine": "Divine Orb", "stackSize": 5},
+                {"id": "c1", "frameType": 6, "typeLine": "The Doctor", "stackSize": 1}]"#,
+        );
+        let changes = diff(&a, &b);
+        let summary: Vec<(&str, i64)> =
+            changes.iter().map(|c| (c.name.as_str(), c.delta)).collect();
+        assert_eq!(
+            summary,
+            [("Divine Orb", 3), ("The Doctor", 1), ("Goldwyrm", -1)]
+        );
+
+        let table = PriceTable::from_lines(
+            serde_json::from_str::<Vec<PriceLine>>(
+                r#"[{"currencyTypeName": "Divine Orb", "chaosEquivalent": 100.0},
+                    {"name": "Goldwyrm", "chaosValue": 20.0}]"#,
+            )
+            .unwrap(),
+        );
+        let out = report(&a, &b, Some(&table));
+        assert!(out.contains("Added (2):"), "{out}");
+        assert!(out.contains("Removed (1):"), "{out}");
+        assert!(
+            out.ends_with("Net value: +280.0 chaos (+560.0 chaos/hour)\n"),
+            "{out}"
+        );
+        assert!(!report(&a, &b, None).contains("Net value"));
+    }
+
+    #[test]
+    fn snapshots_round_trip_compressed_and_expire() {
+        let tmp = TempDir::new("snapshots");
+        let root = tmp.path();
+        let stale = snap(1, "[]");
+        save(root, "Standard", &stale).unwrap();
+        let fresh = snap(now(), r#"[{"id": "x", "typeLine": "Chaos Orb"}]"#);
+        let path = save(root, "Standard", &fresh).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..2], [0x1f, 0x8b], "gzip magic");
+        assert_eq!(load(&path).unwrap().tabs, fresh.tabs);
+        let left: Vec<u64> = entries(root, "Standard").iter().map(|e| e.0).collect();
+        assert_eq!(left, [fresh.saved_at], "two-week-old snapshot is pruned");
+    }
+}
@@ -1,5 +1,10 @@
 //! Test-only helpers shared by in-crate tests. Compiled only under `cargo test`.
+use parking_lot::Mutex;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Every `.txt` file under `tests/example_items/`, recursively, sorted.
 /// Directories named `broken` are skipped: known-unparseable pastes live there
@@ -29,3 +34,87 @@ pub(crate) fn example_item_files() -> Vec<PathBuf> {
     walk(&root, &mut out);
     out
 }
+
+/// A fresh directory under the system temp dir, removed on drop. Unique per
+/// process and call, so parallel tests never share one.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(tag: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("little_oil_{tag}_{}_{n}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// One canned reply served by [`mock_http`].
+pub(crate) struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str, &'static str)>,
+    pub body: &'static str,
+}
+
+impl MockResponse {
+    pub(crate) fn ok(body: &'static str) -> Self {
+        MockResponse {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        }
+    }
+}
+
+/// A local HTTP/1.1 server that answers one connection per canned response,
+/// in order, then exits. Returns the base URL (`http://127.0.0.1:<port>`)
+/// and the request heads it received, so tests can assert on paths and
+/// headers. Every reply closes its connection.
+pub(crate) fn mock_http(responses: Vec<MockResponse>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    std::thread::spawn(move || {
+        for resp in responses {
+            let Ok((stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            log.lock().push(head);
+            let mut out = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                resp.status,
+                resp.body.len()
+            );
+            for (k, v) in &resp.headers {
+                out.push_str(&format!("{k}: {v}\r\n"));
+            }
+            out.push_str("\r\n");
+            out.push_str(resp.body);
+            let mut stream = reader.into_inner();
+            let _ = stream.write_all(out.as_bytes());
+        }
+    });
+    (base, seen)
+}
@@ -0,0 +1,242 @@
+//! Vendor recipes besides chaos/regal, found in one stash tab's API items:
+//!
+//! - gemcutter's prism: gems whose quality adds up to 40%
+//! - glassblower's bauble: flasks whose quality adds up to 40%
+//! - chromatic orb: an item with linked red, green and blue sockets
+//! - jeweller's orb: an item with six sockets
+//!
+//! Quality sets are a subset-sum problem, solved repeatedly for the set with
+//! the fewest items so the remaining items can still form more sets. Sets
+//! are clicked out with the chaos recipe's [`TabClicker`].
+use crate::chaos_recipe::{self, ChaosRecipe, TabClicker};
+use crate::pricing::ApiItem;
+
+/// Quality a gem or flask set has to add up to.
+const QUALITY_TARGET: u32 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VendorRecipe {
+    Gemcutter,
+    Bauble,
+    Chromatic,
+    Jeweller,
+}
+
+impl VendorRecipe {
+    pub fn from_name(name: &str) -> anyhow::Result<VendorRecipe> {
+        match name {
+            "gcp" | "gemcutter" => Ok(VendorRecipe::Gemcutter),
+            "bauble" | "glassblower" => Ok(VendorRecipe::Bauble),
+            "chromatic" | "chrome" => Ok(VendorRecipe::Chromatic),
+            "jeweller" | "jew" => Ok(VendorRecipe::Jeweller),
+            _ => anyhow::bail!("Unknown recipe '{name}' — use gcp, bauble, chromatic or jeweller"),
+        }
+    }
+
+    fn reward(self) -> &'static str {
+        match self {
+            VendorRecipe::Gemcutter => "Gemcutter's Prism",
+            VendorRecipe::Bauble => "Glassblower's Bauble",
+            VendorRecipe::Chromatic => "Chromatic Orb",
+            VendorRecipe::Jeweller => "Jeweller's Orb",
+        }
+    }
+}
+
+fn quality(item: &ApiItem) -> u32 {
+    item.property("Quality").into()
+}
+
+fn is_gem(item: &ApiItem) -> bool {
+    item.frame_type == 4
+}
+
+fn is_flask(item: &ApiItem) -> bool {
+    item.type_line.contains("Flask")
+}
+
+/// Some link group holds a red, a green and a blue socket.
+fn has_rgb_link(item: &ApiItem) -> bool {
+    item.sockets.iter().any(|first| {
+        ["R", "G", "B"].iter().all(|c| {
+            item.sockets
+                .iter()
+                .any(|s| s.group == first.group && s.colour == *c)
+        })
+    })
+}
+
+/// Disjoint sets of `items` (index, quality) that add up to exactly
+/// `target`. Each round takes the smallest such set, leaving as many items
+/// as possible for later rounds.
+fn quality_sets(items: &[(usize, u32)], target: u32) -> Vec<Vec<usize>> {
+    let mut left: Vec<(usize, u32)> = items
+        .iter()
+        .copied()
+        .filter(|&(_, q)| q > 0 && q <= target)
+        .collect();
+    left.sort_by_key(|&(i, q)| (std::cmp::Reverse(q), i));
+    let target = target as usize;
+    let mut sets = Vec::new();
+    loop {
+        // best[s]: fewest items (positions in `left`) adding up to s.
+        let mut best: Vec<Option<Vec<usize>>> = vec![None; target + 1];
+        best[0] = Some(Vec::new());
+        for (pos, &(_, q)) in left.iter().enumerate() {
+            let q = q as usize;
+            for s in (q..=target).rev() {
+                let Some(prev) = &best[s - q] else {
+                    continue;
+                };
+                if best[s].as_ref().is_none_or(|b| b.len() > prev.len() + 1) {
+                    let mut next = prev.clone();
+                    next.push(pos);
+                    best[s] = Some(next);
+                }
+            }
+        }
+        let Some(set) = best[target].take() else {
+            break;
+        };
+        sets.push(set.iter().map(|&pos| left[pos].0).collect());
+        let mut pos = 0;
+        left.retain(|_| {
+            pos += 1;
+            !set.contains(&(pos - 1))
+        });
+    }
+    sets
+}
+
+/// Every set `recipe` can make from `items`, as indices into `items`.
+pub fn find_sets(recipe: VendorRecipe, items: &[ApiItem]) -> Vec<Vec<usize>> {
+    let singles = |keep: fn(&ApiItem) -> bool| {
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| keep(item))
+            .map(|(i, _)| vec![i])
+            .collect()
+    };
+    let by_quality = |keep: fn(&ApiItem) -> bool| {
+        let qualities: Vec<(usize, u32)> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| keep(item))
+            .map(|(i, item)| (i, quality(item)))
+            .collect();
+        quality_sets(&qualities, QUALITY_TARGET)
+    };
+    match recipe {
+        VendorRecipe::Gemcutter => by_quality(is_gem),
+        VendorRecipe::Bauble => by_quality(is_flask),
+        VendorRecipe::Chromatic => singles(has_rgb_link),
+        VendorRecipe::Jeweller => singles(|item| item.sockets.len() == 6),
+    }
+}
+
+/// `recipe <kind> [sets]`: list every set in `tab` and click the first
+/// `sets` of them out of the tab (none when `sets` is 0).
+pub fn run(
+    app: &crate::App,
+    cr_config: &ChaosRecipe,
+    recipe: VendorRecipe,
+    tab: &str,
+    sets: usize,
+) -> anyhow::Result<()> {
+    let items: Vec<ApiItem> = chaos_recipe::tab_items(cr_config, tab)?;
+    let found = find_sets(recipe, &items);
+    println!("{} {} set(s) in {tab}", found.len(), recipe.reward());
+    for (n, set) in found.iter().enumerate() {
+        let names: Vec<String> = set
+            .iter()
+            .map(|&i| match recipe {
+                VendorRecipe::Gemcutter | VendorRecipe::Bauble => {
+                    format!("{} ({}%)", items[i].type_line, quality(&items[i]))
+                }
+                _ => items[i].type_line.clone(),
+            })
+            .collect();
+        println!("  {}: {}", n + 1, names.join(", "));
+    }
+    if sets == 0 || found.is_empty() {
+        return Ok(());
+    }
+
+    let quad = chaos_recipe::tab_is_quad(cr_config, tab)?;
+    app.focus_game_window()?;
+    let clicker = TabClicker::new(app, quad)?;
+    for set in found.iter().take(sets) {
+        for &i in set {
+            clicker.click(app, items[i].x, items[i].y);
+        }
+    }
+    println!("Took {} set(s)", sets.min(found.len()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(json: &str) -> Vec<ApiItem> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn gem(q: u32) -> String {
+        format!(
+            r#"{{"frameType": 4, "typeLine": "Gem {q}", "properties": [{{"name": "Quality", "values": [["+{q}%", 1]]}}]}}"#
+        )
+    }
+
+    #[test]
+    fn quality_sets_are_exact_disjoint_and_smallest_first() {
+        let sets = quality_sets(
+            &[(0, 20), (1, 20), (2, 10), (3, 15), (4, 15), (5, 5), (6, 3)],
+            40,
+        );
+        assert_eq!(sets, [vec![0, 1], vec![3, 4, 2]]);
+        assert!(quality_sets(&[(0, 19), (1, 20)], 40).is_empty());
+    }
+
+    #[test]
+    fn gem_and_flask_recipes_only_use_their_items() {
+        let flask = r#"{"frameType": 1, "typeLine": "Seething Divine Life Flask", "properties": [{"name": "Quality", "values": [["+20%", 1]]}]}"#;
+        let list = items(&format!(
+            "[{}, {}, {flask}, {flask}, {}]",
+            gem(20),
+            gem(13),
+            gem(7)
+        ));
+        assert_eq!(find_sets(VendorRecipe::Gemcutter, &list), [vec![0, 1, 4]]);
+        // A second 20% gem pairs with the first; 13 + 7 is left over.
+        let list = items(&format!(
+            "[{}, {}, {flask}, {flask}, {}, {}]",
+            gem(20),
+            gem(13),
+            gem(7),
+            gem(20)
+        ));
+        assert_eq!(find_sets(VendorRecipe::Gemcutter, &list), [vec![0, 5]]);
+        assert_eq!(find_sets(VendorRecipe::Bauble, &list), [vec![2, 3]]);
+    }
+
+    #[test]
+    fn socket_recipes_check_links_and_count() {
+        let socket = |g: u8, c: &str| format!(r#"{{"group": {g}, "sColour": "{c}"}}"#);
+        let item = |sockets: Vec<String>| {
+            format!(
+                r#"{{"frameType": 2, "typeLine": "x", "sockets": [{}]}}"#,
+                sockets.join(",")
+            )
+        };
+        let list = items(&format!(
+            "[{}, {}, {}]",
+            item(vec![socket(0, "R"), socket(0, "G"), socket(1, "B")]),
+            item(vec![socket(0, "B"), socket(0, "R"), socket(0, "G")]),
+            item((0..6).map(|i| socket(i / 2, "R")).collect()),
+        ));
+        assert_eq!(find_sets(VendorRecipe::Chromatic, &list), [vec![1]]);
+        assert_eq!(find_sets(VendorRecipe::Jeweller, &list), [vec![2]]);
+    }
+}